@@ -0,0 +1,3 @@
+ALTER TABLE products DROP CONSTRAINT products_state_check;
+ALTER TABLE products DROP COLUMN published_at;
+ALTER TABLE products DROP COLUMN state;
@@ -0,0 +1,5 @@
+ALTER TABLE products ADD COLUMN state VARCHAR NOT NULL DEFAULT 'published';
+ALTER TABLE products ADD COLUMN published_at TIMESTAMP;
+UPDATE products SET published_at = NOW();
+ALTER TABLE products ALTER COLUMN state SET DEFAULT 'draft';
+ALTER TABLE products ADD CONSTRAINT products_state_check CHECK (state IN ('draft', 'published', 'archived'));
@@ -0,0 +1,3 @@
+DROP TRIGGER IF EXISTS set_updated_at ON products;
+ALTER TABLE products DROP COLUMN updated_at;
+ALTER TABLE products DROP COLUMN created_at;
@@ -0,0 +1,3 @@
+ALTER TABLE products ADD COLUMN created_at TIMESTAMP NOT NULL DEFAULT NOW();
+ALTER TABLE products ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT NOW();
+SELECT diesel_manage_updated_at('products');
@@ -0,0 +1,25 @@
+CREATE TABLE product_revisions (
+  id SERIAL PRIMARY KEY,
+  product_id INTEGER REFERENCES products (id) ON DELETE CASCADE NOT NULL,
+  number INTEGER NOT NULL,
+  user_id INTEGER REFERENCES users (id) ON DELETE CASCADE NOT NULL,
+  title VARCHAR NOT NULL,
+  body TEXT NOT NULL,
+  simple VARCHAR NOT NULL,
+  img VARCHAR NOT NULL,
+  kind VARCHAR NOT NULL,
+  status VARCHAR NOT NULL,
+  duration INTEGER NOT NULL,
+  tag_ids INTEGER[] NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  UNIQUE (product_id, number)
+);
+
+-- Existing products start their history with their current contents.
+INSERT INTO product_revisions (product_id, number, user_id, title, body, simple, img, kind, status, duration, tag_ids, created_at)
+SELECT
+  products.id, 1, products.user_id, products.title, products.body, products.simple, products.img,
+  products.kind, products.status, products.duration,
+  COALESCE((SELECT array_agg(tag_id ORDER BY tag_id) FROM products_tags WHERE product_id = products.id), '{}'),
+  products.updated_at
+FROM products;
@@ -0,0 +1,3 @@
+DROP TABLE product_slugs;
+ALTER TABLE products DROP CONSTRAINT products_user_id_slug_key;
+ALTER TABLE products DROP COLUMN slug;
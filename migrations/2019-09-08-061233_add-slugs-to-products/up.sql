@@ -0,0 +1,22 @@
+ALTER TABLE products ADD COLUMN slug VARCHAR;
+UPDATE products SET slug = COALESCE(
+  NULLIF(trim(both '-' from lower(regexp_replace(title, '[^a-zA-Z0-9]+', '-', 'g'))), ''),
+  'product-' || substr(replace(uuid::text, '-', ''), 1, 8)
+);
+UPDATE products SET slug = products.slug || '-' || products.id
+WHERE EXISTS (
+  SELECT 1 FROM products other
+  WHERE other.user_id = products.user_id AND other.slug = products.slug AND other.id < products.id
+);
+ALTER TABLE products ALTER COLUMN slug SET NOT NULL;
+ALTER TABLE products ADD CONSTRAINT products_user_id_slug_key UNIQUE (user_id, slug);
+
+-- Slugs a product used before being renamed, kept around for redirects.
+CREATE TABLE product_slugs (
+  id SERIAL PRIMARY KEY,
+  product_id INTEGER REFERENCES products (id) ON DELETE CASCADE NOT NULL,
+  user_id INTEGER REFERENCES users (id) ON DELETE CASCADE NOT NULL,
+  slug VARCHAR NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  UNIQUE (user_id, slug)
+);
@@ -0,0 +1,12 @@
+CREATE TABLE comments (
+  id SERIAL PRIMARY KEY,
+  product_id INTEGER REFERENCES products (id) ON DELETE CASCADE NOT NULL,
+  user_id INTEGER REFERENCES users (id) ON DELETE CASCADE NOT NULL,
+  parent_id INTEGER REFERENCES comments (id) ON DELETE CASCADE,
+  body TEXT NOT NULL,
+  deleted_at TIMESTAMP,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+CREATE INDEX comments_product_id_idx ON comments (product_id);
+SELECT diesel_manage_updated_at('comments');
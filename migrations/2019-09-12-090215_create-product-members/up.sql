@@ -0,0 +1,14 @@
+CREATE TABLE product_members (
+  id SERIAL PRIMARY KEY,
+  product_id INTEGER REFERENCES products (id) ON DELETE CASCADE NOT NULL,
+  user_id INTEGER REFERENCES users (id) ON DELETE CASCADE NOT NULL,
+  role VARCHAR NOT NULL CHECK (role IN ('owner', 'editor')),
+  invited_by INTEGER REFERENCES users (id) ON DELETE SET NULL,
+  accepted_at TIMESTAMP,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  UNIQUE (product_id, user_id)
+);
+
+-- Every existing product is owned by its author.
+INSERT INTO product_members (product_id, user_id, role, accepted_at, created_at)
+SELECT id, user_id, 'owner', created_at, created_at FROM products;
@@ -0,0 +1,3 @@
+DROP TABLE collection_items;
+DROP TABLE collections;
+DROP TABLE bookmarks;
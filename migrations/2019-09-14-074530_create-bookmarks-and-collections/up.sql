@@ -0,0 +1,28 @@
+CREATE TABLE bookmarks (
+  id SERIAL PRIMARY KEY,
+  user_id INTEGER REFERENCES users (id) ON DELETE CASCADE NOT NULL,
+  product_id INTEGER REFERENCES products (id) ON DELETE CASCADE NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  UNIQUE (user_id, product_id)
+);
+
+CREATE TABLE collections (
+  id SERIAL PRIMARY KEY,
+  uuid UUID NOT NULL UNIQUE,
+  user_id INTEGER REFERENCES users (id) ON DELETE CASCADE NOT NULL,
+  name VARCHAR NOT NULL,
+  description TEXT NOT NULL DEFAULT '',
+  public BOOLEAN NOT NULL DEFAULT FALSE,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+SELECT diesel_manage_updated_at('collections');
+
+CREATE TABLE collection_items (
+  id SERIAL PRIMARY KEY,
+  collection_id INTEGER REFERENCES collections (id) ON DELETE CASCADE NOT NULL,
+  product_id INTEGER REFERENCES products (id) ON DELETE CASCADE NOT NULL,
+  position INTEGER NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  UNIQUE (collection_id, product_id)
+);
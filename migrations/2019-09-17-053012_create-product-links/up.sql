@@ -0,0 +1,11 @@
+CREATE TABLE product_links (
+  id SERIAL PRIMARY KEY,
+  product_id INTEGER REFERENCES products (id) ON DELETE CASCADE NOT NULL,
+  kind VARCHAR NOT NULL CHECK (kind IN ('repo', 'demo', 'docs', 'other')),
+  url VARCHAR NOT NULL,
+  status INTEGER,
+  error VARCHAR,
+  checked_at TIMESTAMP,
+  UNIQUE (product_id, kind)
+);
+CREATE INDEX product_links_checked_at_idx ON product_links (checked_at);
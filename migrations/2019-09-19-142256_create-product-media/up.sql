@@ -0,0 +1,14 @@
+CREATE TABLE product_media (
+  id SERIAL PRIMARY KEY,
+  uuid UUID NOT NULL UNIQUE,
+  product_id INTEGER REFERENCES products (id) ON DELETE CASCADE NOT NULL,
+  kind VARCHAR NOT NULL CHECK (kind IN ('image', 'video')),
+  storage_key VARCHAR NOT NULL UNIQUE,
+  caption VARCHAR NOT NULL DEFAULT '',
+  alt VARCHAR NOT NULL DEFAULT '',
+  width INTEGER NOT NULL,
+  height INTEGER NOT NULL,
+  position INTEGER NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+CREATE INDEX product_media_product_id_idx ON product_media (product_id, position);
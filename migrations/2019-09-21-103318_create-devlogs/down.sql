@@ -0,0 +1,2 @@
+ALTER TABLE products DROP COLUMN duration_from_devlogs;
+DROP TABLE devlogs;
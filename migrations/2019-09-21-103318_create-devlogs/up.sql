@@ -0,0 +1,15 @@
+CREATE TABLE devlogs (
+  id SERIAL PRIMARY KEY,
+  uuid UUID NOT NULL UNIQUE,
+  product_id INTEGER REFERENCES products (id) ON DELETE CASCADE NOT NULL,
+  user_id INTEGER REFERENCES users (id) ON DELETE CASCADE NOT NULL,
+  body TEXT NOT NULL,
+  hours INTEGER CHECK (hours >= 0),
+  img VARCHAR,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+SELECT diesel_manage_updated_at('devlogs');
+CREATE INDEX devlogs_product_id_idx ON devlogs (product_id, created_at);
+
+ALTER TABLE products ADD COLUMN duration_from_devlogs BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,3 @@
+ALTER TABLE devlogs DROP COLUMN body_html;
+ALTER TABLE comments DROP COLUMN body_html;
+ALTER TABLE products DROP COLUMN body_html;
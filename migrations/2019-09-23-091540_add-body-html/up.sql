@@ -0,0 +1,4 @@
+-- Existing bodies are rendered once by `cargo run --bin render_html`.
+ALTER TABLE products ADD COLUMN body_html TEXT NOT NULL DEFAULT '';
+ALTER TABLE comments ADD COLUMN body_html TEXT NOT NULL DEFAULT '';
+ALTER TABLE devlogs ADD COLUMN body_html TEXT NOT NULL DEFAULT '';
@@ -0,0 +1,9 @@
+CREATE TABLE product_views (
+  id SERIAL PRIMARY KEY,
+  product_id INTEGER REFERENCES products (id) ON DELETE CASCADE NOT NULL,
+  viewer VARCHAR NOT NULL,
+  referrer VARCHAR,
+  day DATE NOT NULL DEFAULT CURRENT_DATE,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  UNIQUE (product_id, viewer, day)
+);
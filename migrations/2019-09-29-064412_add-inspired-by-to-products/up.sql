@@ -0,0 +1,2 @@
+ALTER TABLE products ADD COLUMN inspired_by_id INTEGER REFERENCES products (id) ON DELETE SET NULL;
+CREATE INDEX products_inspired_by_id_idx ON products (inspired_by_id);
@@ -0,0 +1,2 @@
+ALTER TABLE reactions DROP CONSTRAINT reactions_kind_fkey;
+DROP TABLE reaction_kinds;
@@ -0,0 +1,18 @@
+CREATE TABLE reaction_kinds (
+  name VARCHAR PRIMARY KEY,
+  emoji VARCHAR NOT NULL,
+  label VARCHAR NOT NULL,
+  position INTEGER NOT NULL
+);
+INSERT INTO reaction_kinds (name, emoji, label, position) VALUES
+  ('like', '👍', 'Like', 1),
+  ('love', '❤️', 'Love', 2),
+  ('clap', '👏', 'Clap', 3),
+  ('fire', '🔥', 'Fire', 4),
+  ('idea', '💡', 'Inspiring', 5),
+  ('rocket', '🚀', 'Ship it', 6);
+
+-- Kinds invented by clients before the catalogue existed count as likes.
+UPDATE reactions SET kind = 'like' WHERE kind NOT IN (SELECT name FROM reaction_kinds);
+ALTER TABLE reactions
+  ADD CONSTRAINT reactions_kind_fkey FOREIGN KEY (kind) REFERENCES reaction_kinds (name) ON UPDATE CASCADE;
@@ -0,0 +1,4 @@
+DROP INDEX reactions_user_id_product_id_idx;
+DROP TRIGGER count_reactions ON reactions;
+DROP FUNCTION count_reactions();
+DROP TABLE reaction_counts;
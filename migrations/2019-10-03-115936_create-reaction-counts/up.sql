@@ -0,0 +1,29 @@
+CREATE TABLE reaction_counts (
+  product_id INTEGER REFERENCES products (id) ON DELETE CASCADE NOT NULL,
+  kind VARCHAR REFERENCES reaction_kinds (name) ON UPDATE CASCADE NOT NULL,
+  count BIGINT NOT NULL DEFAULT 0,
+  PRIMARY KEY (product_id, kind)
+);
+
+INSERT INTO reaction_counts (product_id, kind, count)
+  SELECT product_id, kind, COUNT(*) FROM reactions GROUP BY product_id, kind;
+
+-- Keeps reaction_counts in step with the rows of reactions.
+CREATE FUNCTION count_reactions() RETURNS trigger AS $$
+BEGIN
+  IF TG_OP = 'INSERT' THEN
+    INSERT INTO reaction_counts (product_id, kind, count) VALUES (NEW.product_id, NEW.kind, 1)
+      ON CONFLICT (product_id, kind) DO UPDATE SET count = reaction_counts.count + 1;
+    RETURN NEW;
+  ELSE
+    UPDATE reaction_counts SET count = count - 1
+      WHERE product_id = OLD.product_id AND kind = OLD.kind;
+    RETURN OLD;
+  END IF;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER count_reactions AFTER INSERT OR DELETE ON reactions
+  FOR EACH ROW EXECUTE PROCEDURE count_reactions();
+
+CREATE INDEX reactions_user_id_product_id_idx ON reactions (user_id, product_id);
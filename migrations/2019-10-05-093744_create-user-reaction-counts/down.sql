@@ -0,0 +1,2 @@
+DROP TABLE user_reaction_counts;
+ALTER TABLE reaction_kinds DROP COLUMN max_per_user;
@@ -0,0 +1,14 @@
+ALTER TABLE reaction_kinds ADD COLUMN max_per_user INTEGER NOT NULL DEFAULT 5;
+
+-- One row per user, product and kind, so that concurrent reactions queue up
+-- on the same row while the limit is checked.
+CREATE TABLE user_reaction_counts (
+  user_id INTEGER REFERENCES users (id) ON DELETE CASCADE NOT NULL,
+  product_id INTEGER REFERENCES products (id) ON DELETE CASCADE NOT NULL,
+  kind VARCHAR REFERENCES reaction_kinds (name) ON UPDATE CASCADE NOT NULL,
+  count INTEGER NOT NULL CHECK (count >= 0),
+  PRIMARY KEY (user_id, product_id, kind)
+);
+
+INSERT INTO user_reaction_counts (user_id, product_id, kind, count)
+  SELECT user_id, product_id, kind, COUNT(*) FROM reactions GROUP BY user_id, product_id, kind;
@@ -0,0 +1,2 @@
+DROP INDEX reactions_created_at_idx;
+DROP TABLE trending_scores;
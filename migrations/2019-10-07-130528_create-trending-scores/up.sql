@@ -0,0 +1,9 @@
+CREATE TABLE trending_scores (
+  product_id INTEGER REFERENCES products (id) ON DELETE CASCADE NOT NULL,
+  period VARCHAR NOT NULL CHECK (period IN ('24h', '7d', '30d', 'all')),
+  score BIGINT NOT NULL,
+  refreshed_at TIMESTAMP NOT NULL,
+  PRIMARY KEY (period, product_id)
+);
+CREATE INDEX trending_scores_period_score_idx ON trending_scores (period, score DESC);
+CREATE INDEX reactions_created_at_idx ON reactions (created_at);
@@ -0,0 +1 @@
+ALTER TABLE reaction_kinds DROP COLUMN weight;
@@ -0,0 +1 @@
+ALTER TABLE reaction_kinds ADD COLUMN weight INTEGER NOT NULL DEFAULT 1 CHECK (weight >= 0);
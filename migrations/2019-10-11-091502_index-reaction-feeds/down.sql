@@ -0,0 +1,2 @@
+DROP INDEX reactions_product_id_created_at_idx;
+DROP INDEX reactions_user_id_created_at_idx;
@@ -0,0 +1,2 @@
+CREATE INDEX reactions_user_id_created_at_idx ON reactions (user_id, created_at DESC, id DESC);
+CREATE INDEX reactions_product_id_created_at_idx ON reactions (product_id, created_at DESC, id DESC);
@@ -0,0 +1,19 @@
+CREATE TABLE notifications (
+  id SERIAL PRIMARY KEY,
+  uuid UUID NOT NULL UNIQUE,
+  user_id INTEGER REFERENCES users (id) ON DELETE CASCADE NOT NULL,
+  kind VARCHAR NOT NULL,
+  product_id INTEGER REFERENCES products (id) ON DELETE CASCADE,
+  subject VARCHAR NOT NULL DEFAULT '',
+  actor_ids INTEGER[] NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  read_at TIMESTAMP
+);
+
+-- Events of the same kind on the same product are folded into one unread
+-- notification until it is read.
+CREATE UNIQUE INDEX notifications_unread_idx
+  ON notifications (user_id, kind, COALESCE(product_id, 0), subject)
+  WHERE read_at IS NULL;
+CREATE INDEX notifications_user_id_updated_at_idx ON notifications (user_id, updated_at DESC, id DESC);
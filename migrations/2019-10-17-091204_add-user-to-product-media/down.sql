@@ -0,0 +1 @@
+ALTER TABLE product_media DROP COLUMN user_id;
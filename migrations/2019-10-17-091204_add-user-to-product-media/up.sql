@@ -0,0 +1,3 @@
+-- Who uploaded the stored object. Media registered before uploads were
+-- tracked have none, and their objects are never removed.
+ALTER TABLE product_media ADD COLUMN user_id INTEGER REFERENCES users (id) ON DELETE SET NULL;
@@ -0,0 +1,14 @@
+-- Leaves `updated_at` alone while `tentech.keep_updated_at` is set to 'on' by
+-- `SET LOCAL`, for changes which are no edit such as rendering old bodies.
+CREATE OR REPLACE FUNCTION diesel_set_updated_at() RETURNS trigger AS $$
+BEGIN
+    IF (
+        NEW IS DISTINCT FROM OLD AND
+        NEW.updated_at IS NOT DISTINCT FROM OLD.updated_at AND
+        current_setting('tentech.keep_updated_at', true) IS DISTINCT FROM 'on'
+    ) THEN
+        NEW.updated_at := current_timestamp;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1 @@
+ALTER TABLE webhooks ALTER COLUMN events TYPE VARCHAR[];
@@ -0,0 +1,3 @@
+-- Events are looked up with `events @> ARRAY[...]`, whose array is sent as
+-- TEXT[], for which there is no operator against VARCHAR[].
+ALTER TABLE webhooks ALTER COLUMN events TYPE TEXT[];
@@ -0,0 +1,36 @@
+use crate::try_establish_connection;
+use diesel::pg::PgConnection;
+use std::env;
+use std::thread;
+use std::time::Duration;
+
+/// How long to wait before connecting again when the database is unreachable.
+const RETRY: Duration = Duration::from_secs(30);
+
+/// The number in the environment variable `name`, or `default` when it is
+/// unset or not a number.
+pub fn env_u64(name: &str, default: u64) -> u64 {
+    env::var(name)
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(default)
+}
+
+/// The interval of a background job, `name` seconds in the environment.
+pub fn interval(name: &str, default_secs: u64) -> Duration {
+    Duration::from_secs(env_u64(name, default_secs))
+}
+
+/// Connects for a background job, retrying until the database is reachable
+/// again rather than bringing the thread down with it.
+pub fn connect(job: &str) -> PgConnection {
+    loop {
+        match try_establish_connection() {
+            Ok(conn) => return conn,
+            Err(e) => {
+                println!("{} could not connect to the database: {}", job, e);
+                thread::sleep(RETRY);
+            }
+        }
+    }
+}
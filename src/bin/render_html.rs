@@ -0,0 +1,13 @@
+use tentech_api;
+
+/// Renders the bodies written before markdown was rendered on the server.
+/// Run it once after migrating; the server itself never rescans them.
+fn main() {
+    let conn = tentech_api::establish_connection();
+    for table in tentech_api::db::html::TABLES.iter() {
+        match tentech_api::db::html::render_missing(&conn, table) {
+            Ok(rendered) => println!("Rendered {} {}", rendered, table),
+            Err(e) => println!("{:?}", e),
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use crate::models::product::{Product, PUBLISHED};
+use crate::schema::{bookmarks, products};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+
+pub fn add(conn: &PgConnection, user_id: &i32, product_id: &i32) -> Result<usize, Error> {
+    diesel::insert_into(bookmarks::table)
+        .values((
+            bookmarks::user_id.eq(user_id),
+            bookmarks::product_id.eq(product_id),
+        ))
+        .on_conflict_do_nothing()
+        .execute(conn)
+}
+
+pub fn remove(conn: &PgConnection, user_id: &i32, product_id: &i32) -> Result<usize, Error> {
+    diesel::delete(
+        bookmarks::table
+            .filter(bookmarks::user_id.eq(user_id))
+            .filter(bookmarks::product_id.eq(product_id)),
+    )
+    .execute(conn)
+}
+
+/// Loads the bookmarked products which are still published, latest first.
+pub fn find_products_by_user_id(conn: &PgConnection, user_id: &i32) -> Result<Vec<Product>, Error> {
+    bookmarks::table
+        .inner_join(products::table)
+        .select(products::all_columns)
+        .filter(bookmarks::user_id.eq(user_id))
+        .filter(products::state.eq(PUBLISHED))
+        .filter(products::deleted_at.is_null())
+        .order(bookmarks::created_at.desc())
+        .load::<Product>(conn)
+}
@@ -0,0 +1,154 @@
+use crate::models::collection::Collection;
+use crate::models::product::{Product, PUBLISHED};
+use crate::schema::{collection_items, collections, products};
+use diesel::dsl::max;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+use uuid::Uuid;
+
+#[derive(Insertable, AsChangeset)]
+#[table_name = "collections"]
+pub struct NewCollection<'a> {
+    pub name: &'a str,
+    pub description: &'a str,
+    pub public: &'a bool,
+}
+
+pub fn create(
+    conn: &PgConnection,
+    user_id: &i32,
+    new_collection: &NewCollection,
+) -> Result<Collection, Error> {
+    diesel::insert_into(collections::table)
+        .values((
+            new_collection,
+            collections::user_id.eq(user_id),
+            collections::uuid.eq(Uuid::new_v4()),
+        ))
+        .get_result::<Collection>(conn)
+}
+
+pub fn update(
+    conn: &PgConnection,
+    id: &i32,
+    new_collection: &NewCollection,
+) -> Result<Collection, Error> {
+    diesel::update(collections::table.find(id))
+        .set(new_collection)
+        .get_result::<Collection>(conn)
+}
+
+pub fn delete(conn: &PgConnection, id: &i32) -> Result<usize, Error> {
+    diesel::delete(collections::table.find(id)).execute(conn)
+}
+
+pub fn find(conn: &PgConnection, uuid: &Uuid) -> Result<Collection, Error> {
+    collections::table
+        .filter(collections::uuid.eq(uuid))
+        .first::<Collection>(conn)
+}
+
+pub fn find_by_user_id(
+    conn: &PgConnection,
+    user_id: &i32,
+    include_private: bool,
+) -> Result<Vec<Collection>, Error> {
+    let mut query = collections::table
+        .filter(collections::user_id.eq(user_id))
+        .into_boxed();
+    if !include_private {
+        query = query.filter(collections::public.eq(true));
+    }
+    query
+        .order(collections::updated_at.desc())
+        .load::<Collection>(conn)
+}
+
+/// Loads the published products of the collection in their saved order.
+pub fn find_products(conn: &PgConnection, collection_id: &i32) -> Result<Vec<Product>, Error> {
+    collection_items::table
+        .inner_join(products::table)
+        .select(products::all_columns)
+        .filter(collection_items::collection_id.eq(collection_id))
+        .filter(products::state.eq(PUBLISHED))
+        .filter(products::deleted_at.is_null())
+        .order((collection_items::position.asc(), collection_items::id.asc()))
+        .load::<Product>(conn)
+}
+
+pub fn find_product_ids(conn: &PgConnection, collection_id: &i32) -> Result<Vec<i32>, Error> {
+    collection_items::table
+        .select(collection_items::product_id)
+        .filter(collection_items::collection_id.eq(collection_id))
+        .order((collection_items::position.asc(), collection_items::id.asc()))
+        .load::<i32>(conn)
+}
+
+/// Appends the product to the end of the collection.
+pub fn add_item(
+    conn: &PgConnection,
+    collection_id: &i32,
+    product_id: &i32,
+) -> Result<usize, Error> {
+    let last = collection_items::table
+        .filter(collection_items::collection_id.eq(collection_id))
+        .select(max(collection_items::position))
+        .first::<Option<i32>>(conn)?;
+    diesel::insert_into(collection_items::table)
+        .values((
+            collection_items::collection_id.eq(collection_id),
+            collection_items::product_id.eq(product_id),
+            collection_items::position.eq(last.unwrap_or(0) + 1),
+        ))
+        .on_conflict_do_nothing()
+        .execute(conn)
+}
+
+/// Removes the item by its own id.
+pub fn remove_item(conn: &PgConnection, collection_id: &i32, id: &i32) -> Result<usize, Error> {
+    diesel::delete(
+        collection_items::table
+            .filter(collection_items::collection_id.eq(collection_id))
+            .filter(collection_items::id.eq(id)),
+    )
+    .execute(conn)
+}
+
+/// Removes the item of the product, whatever the state of the product, so that
+/// items in the trash can be removed as well.
+pub fn remove_product(
+    conn: &PgConnection,
+    collection_id: &i32,
+    product_uuid: &Uuid,
+) -> Result<usize, Error> {
+    let product_ids = products::table
+        .select(products::id)
+        .filter(products::uuid.eq(product_uuid));
+    diesel::delete(
+        collection_items::table
+            .filter(collection_items::collection_id.eq(collection_id))
+            .filter(collection_items::product_id.eq_any(product_ids)),
+    )
+    .execute(conn)
+}
+
+/// Stores `product_ids` as the new order of the collection's items.
+pub fn reorder(
+    conn: &PgConnection,
+    collection_id: &i32,
+    product_ids: &Vec<i32>,
+) -> Result<(), Error> {
+    conn.transaction(|| {
+        for (position, product_id) in product_ids.iter().enumerate() {
+            diesel::update(
+                collection_items::table
+                    .filter(collection_items::collection_id.eq(collection_id))
+                    .filter(collection_items::product_id.eq(product_id)),
+            )
+            .set(collection_items::position.eq(position as i32 + 1))
+            .execute(conn)?;
+        }
+        Ok(())
+    })
+}
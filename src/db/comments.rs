@@ -0,0 +1,82 @@
+use crate::markdown;
+use crate::models::comment::Comment;
+use crate::schema::comments;
+use diesel::dsl::sql;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::query_dsl::GroupByDsl;
+use diesel::result::Error;
+use diesel::sql_types::BigInt;
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+#[derive(Insertable)]
+#[table_name = "comments"]
+pub struct NewComment<'a> {
+    pub product_id: &'a i32,
+    pub user_id: &'a i32,
+    pub parent_id: Option<&'a i32>,
+    pub body: &'a str,
+    pub body_html: &'a str,
+}
+
+pub fn create(
+    conn: &PgConnection,
+    product_id: &i32,
+    user_id: &i32,
+    parent_id: Option<&i32>,
+    body: &str,
+) -> Result<Comment, Error> {
+    let new_comment = &NewComment {
+        product_id,
+        user_id,
+        parent_id,
+        body,
+        body_html: &markdown::render(body),
+    };
+
+    diesel::insert_into(comments::table)
+        .values(new_comment)
+        .get_result::<Comment>(conn)
+}
+
+pub fn find(conn: &PgConnection, id: &i32) -> Result<Comment, Error> {
+    comments::table.find(id).first::<Comment>(conn)
+}
+
+/// Loads every comment of the product, oldest first, including tombstones.
+pub fn find_by_product_id(conn: &PgConnection, product_id: &i32) -> Result<Vec<Comment>, Error> {
+    comments::table
+        .filter(comments::product_id.eq(product_id))
+        .order((comments::created_at.asc(), comments::id.asc()))
+        .load::<Comment>(conn)
+}
+
+pub fn update(conn: &PgConnection, id: &i32, body: &str) -> Result<Comment, Error> {
+    diesel::update(comments::table.find(id))
+        .set((
+            comments::body.eq(body),
+            comments::body_html.eq(markdown::render(body)),
+        ))
+        .get_result::<Comment>(conn)
+}
+
+pub fn delete(conn: &PgConnection, id: &i32) -> Result<Comment, Error> {
+    diesel::update(comments::table.find(id))
+        .set(comments::deleted_at.eq(SystemTime::now()))
+        .get_result::<Comment>(conn)
+}
+
+/// Counts the comments which are not deleted, keyed by product id.
+pub fn count_by_product_ids(
+    conn: &PgConnection,
+    product_ids: &Vec<i32>,
+) -> Result<HashMap<i32, i64>, Error> {
+    comments::table
+        .select((comments::product_id, sql::<BigInt>("COUNT(*)")))
+        .filter(comments::product_id.eq_any(product_ids))
+        .filter(comments::deleted_at.is_null())
+        .group_by(comments::product_id)
+        .load::<(i32, i64)>(conn)
+        .map(|counts| counts.into_iter().collect())
+}
@@ -0,0 +1,98 @@
+use crate::markdown;
+use crate::models::devlog::Devlog;
+use crate::models::product::Product;
+use crate::schema::{devlogs, products};
+use diesel::dsl::sum;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+use std::convert::TryFrom;
+use uuid::Uuid;
+
+#[derive(Insertable, AsChangeset)]
+#[changeset_options(treat_none_as_null = "true")]
+#[table_name = "devlogs"]
+pub struct NewDevlog<'a> {
+    pub body: &'a str,
+    pub hours: Option<&'a i32>,
+    pub img: Option<&'a str>,
+}
+
+pub fn create(
+    conn: &PgConnection,
+    product_id: &i32,
+    user_id: &i32,
+    new_devlog: &NewDevlog,
+) -> Result<Devlog, Error> {
+    conn.transaction(|| {
+        let devlog = diesel::insert_into(devlogs::table)
+            .values((
+                new_devlog,
+                devlogs::body_html.eq(markdown::render(new_devlog.body)),
+                devlogs::uuid.eq(Uuid::new_v4()),
+                devlogs::product_id.eq(product_id),
+                devlogs::user_id.eq(user_id),
+            ))
+            .get_result::<Devlog>(conn)?;
+        sync_duration(conn, product_id)?;
+        Ok(devlog)
+    })
+}
+
+pub fn update(conn: &PgConnection, id: &i32, new_devlog: &NewDevlog) -> Result<Devlog, Error> {
+    conn.transaction(|| {
+        let devlog = diesel::update(devlogs::table.find(id))
+            .set((
+                new_devlog,
+                devlogs::body_html.eq(markdown::render(new_devlog.body)),
+            ))
+            .get_result::<Devlog>(conn)?;
+        sync_duration(conn, &devlog.product_id)?;
+        Ok(devlog)
+    })
+}
+
+pub fn delete(conn: &PgConnection, devlog: &Devlog) -> Result<(), Error> {
+    conn.transaction(|| {
+        diesel::delete(devlogs::table.find(devlog.id)).execute(conn)?;
+        sync_duration(conn, &devlog.product_id)?;
+        Ok(())
+    })
+}
+
+pub fn find(conn: &PgConnection, uuid: &Uuid) -> Result<Devlog, Error> {
+    devlogs::table
+        .filter(devlogs::uuid.eq(uuid))
+        .first::<Devlog>(conn)
+}
+
+/// Loads the entries of the product, newest first.
+pub fn find_by_product_id(conn: &PgConnection, product_id: &i32) -> Result<Vec<Devlog>, Error> {
+    devlogs::table
+        .filter(devlogs::product_id.eq(product_id))
+        .order((devlogs::created_at.desc(), devlogs::id.desc()))
+        .load::<Devlog>(conn)
+}
+
+/// Sums the logged hours, failing rather than wrapping when they do not fit
+/// the duration of a product.
+pub fn total_hours(conn: &PgConnection, product_id: &i32) -> Result<i32, Error> {
+    let total = devlogs::table
+        .filter(devlogs::product_id.eq(product_id))
+        .select(sum(devlogs::hours))
+        .first::<Option<i64>>(conn)?;
+    i32::try_from(total.unwrap_or(0)).map_err(|e| Error::DeserializationError(Box::new(e)))
+}
+
+/// Sets the duration of the product to the logged hours, when it is derived from them.
+pub fn sync_duration(conn: &PgConnection, product_id: &i32) -> Result<Option<Product>, Error> {
+    let total = total_hours(conn, product_id)?;
+    diesel::update(
+        products::table
+            .filter(products::id.eq(product_id))
+            .filter(products::duration_from_devlogs.eq(true)),
+    )
+    .set(products::duration.eq(total))
+    .get_result::<Product>(conn)
+    .optional()
+}
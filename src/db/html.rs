@@ -0,0 +1,37 @@
+use crate::markdown;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::sql_types::{Integer, Text};
+
+/// The tables whose `body` is rendered to `body_html`.
+pub const TABLES: &[&str] = &["products", "comments", "devlogs"];
+
+#[derive(QueryableByName)]
+struct Missing {
+    #[sql_type = "Integer"]
+    id: i32,
+    #[sql_type = "Text"]
+    body: String,
+}
+
+/// Renders the bodies of `table` stored before they were rendered on write.
+/// Rendering is no edit, so `tentech.keep_updated_at` tells the trigger to
+/// leave `updated_at` as it was, for this transaction only.
+pub fn render_missing(conn: &PgConnection, table: &'static str) -> Result<usize, Error> {
+    conn.transaction(|| {
+        diesel::sql_query("SET LOCAL tentech.keep_updated_at = 'on'").execute(conn)?;
+        let missing = diesel::sql_query(format!(
+            "SELECT id, body FROM {} WHERE body_html = '' AND body <> ''",
+            table
+        ))
+        .load::<Missing>(conn)?;
+        for row in missing.iter() {
+            diesel::sql_query(format!("UPDATE {} SET body_html = $1 WHERE id = $2", table))
+                .bind::<Text, _>(markdown::render(&row.body))
+                .bind::<Integer, _>(row.id)
+                .execute(conn)?;
+        }
+        Ok(missing.len())
+    })
+}
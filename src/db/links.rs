@@ -0,0 +1,89 @@
+use crate::models::link::Link;
+use crate::schema::product_links;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+use std::time::SystemTime;
+
+/// Replaces the links of the product with `links` (pairs of kind and url).
+///
+/// Links whose url did not change keep their last check result.
+pub fn replace(
+    conn: &PgConnection,
+    product_id: &i32,
+    links: &Vec<(String, String)>,
+) -> Result<(), Error> {
+    let current = find_by_product_id(conn, product_id)?;
+    for link in current.iter() {
+        let unchanged = links
+            .iter()
+            .any(|(kind, url)| kind == &link.kind && url == &link.url);
+        if !unchanged {
+            diesel::delete(product_links::table.find(link.id)).execute(conn)?;
+        }
+    }
+    for (kind, url) in links.iter() {
+        let unchanged = current
+            .iter()
+            .any(|link| kind == &link.kind && url == &link.url);
+        if !unchanged {
+            diesel::insert_into(product_links::table)
+                .values((
+                    product_links::product_id.eq(product_id),
+                    product_links::kind.eq(kind),
+                    product_links::url.eq(url),
+                ))
+                .execute(conn)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn find_by_product_id(conn: &PgConnection, product_id: &i32) -> Result<Vec<Link>, Error> {
+    product_links::table
+        .filter(product_links::product_id.eq(product_id))
+        .order(product_links::id.asc())
+        .load::<Link>(conn)
+}
+
+pub fn find_by_product_ids(
+    conn: &PgConnection,
+    product_ids: &Vec<i32>,
+) -> Result<Vec<Link>, Error> {
+    product_links::table
+        .filter(product_links::product_id.eq_any(product_ids))
+        .order(product_links::id.asc())
+        .load::<Link>(conn)
+}
+
+/// Loads links which were never checked or were last checked before `checked_before`.
+pub fn find_stale(
+    conn: &PgConnection,
+    checked_before: SystemTime,
+    limit: i64,
+) -> Result<Vec<Link>, Error> {
+    product_links::table
+        .filter(
+            product_links::checked_at
+                .is_null()
+                .or(product_links::checked_at.lt(checked_before)),
+        )
+        .order(product_links::checked_at.asc().nulls_first())
+        .limit(limit)
+        .load::<Link>(conn)
+}
+
+pub fn record_check(
+    conn: &PgConnection,
+    id: &i32,
+    status: Option<i32>,
+    error: Option<String>,
+) -> Result<usize, Error> {
+    diesel::update(product_links::table.find(id))
+        .set((
+            product_links::status.eq(status),
+            product_links::error.eq(error),
+            product_links::checked_at.eq(SystemTime::now()),
+        ))
+        .execute(conn)
+}
@@ -0,0 +1,75 @@
+use crate::models::media::Media;
+use crate::schema::product_media;
+use diesel::dsl::max;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+use uuid::Uuid;
+
+#[derive(Insertable)]
+#[table_name = "product_media"]
+pub struct NewMedia<'a> {
+    pub kind: &'a str,
+    pub storage_key: &'a str,
+    pub caption: &'a str,
+    pub alt: &'a str,
+    pub width: &'a i32,
+    pub height: &'a i32,
+    pub user_id: Option<&'a i32>,
+}
+
+/// Appends the media to the end of the product's gallery.
+pub fn create(conn: &PgConnection, product_id: &i32, new_media: &NewMedia) -> Result<Media, Error> {
+    let last = product_media::table
+        .filter(product_media::product_id.eq(product_id))
+        .select(max(product_media::position))
+        .first::<Option<i32>>(conn)?;
+    diesel::insert_into(product_media::table)
+        .values((
+            new_media,
+            product_media::uuid.eq(Uuid::new_v4()),
+            product_media::product_id.eq(product_id),
+            product_media::position.eq(last.unwrap_or(0) + 1),
+        ))
+        .get_result::<Media>(conn)
+}
+
+pub fn find(conn: &PgConnection, product_id: &i32, uuid: &Uuid) -> Result<Media, Error> {
+    product_media::table
+        .filter(product_media::product_id.eq(product_id))
+        .filter(product_media::uuid.eq(uuid))
+        .first::<Media>(conn)
+}
+
+pub fn find_by_product_id(conn: &PgConnection, product_id: &i32) -> Result<Vec<Media>, Error> {
+    product_media::table
+        .filter(product_media::product_id.eq(product_id))
+        .order((product_media::position.asc(), product_media::id.asc()))
+        .load::<Media>(conn)
+}
+
+pub fn find_by_storage_key(conn: &PgConnection, storage_key: &str) -> Result<Media, Error> {
+    product_media::table
+        .filter(product_media::storage_key.eq(storage_key))
+        .first::<Media>(conn)
+}
+
+pub fn delete(conn: &PgConnection, id: &i32) -> Result<usize, Error> {
+    diesel::delete(product_media::table.find(id)).execute(conn)
+}
+
+/// Stores `ids` as the new order of the product's gallery.
+pub fn reorder(conn: &PgConnection, product_id: &i32, ids: &Vec<i32>) -> Result<(), Error> {
+    conn.transaction(|| {
+        for (position, id) in ids.iter().enumerate() {
+            diesel::update(
+                product_media::table
+                    .filter(product_media::product_id.eq(product_id))
+                    .filter(product_media::id.eq(id)),
+            )
+            .set(product_media::position.eq(position as i32 + 1))
+            .execute(conn)?;
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,94 @@
+use crate::models::member::{Member, EDITOR, OWNER};
+use crate::schema::product_members;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+use std::time::SystemTime;
+
+#[derive(Insertable)]
+#[table_name = "product_members"]
+pub struct NewMember<'a> {
+    pub product_id: &'a i32,
+    pub user_id: &'a i32,
+    pub role: &'a str,
+    pub invited_by: Option<&'a i32>,
+    pub accepted_at: Option<SystemTime>,
+}
+
+pub fn create_owner(conn: &PgConnection, product_id: &i32, user_id: &i32) -> Result<Member, Error> {
+    let new_member = &NewMember {
+        product_id,
+        user_id,
+        role: OWNER,
+        invited_by: None,
+        accepted_at: Some(SystemTime::now()),
+    };
+
+    diesel::insert_into(product_members::table)
+        .values(new_member)
+        .get_result::<Member>(conn)
+}
+
+pub fn invite(
+    conn: &PgConnection,
+    product_id: &i32,
+    user_id: &i32,
+    invited_by: &i32,
+) -> Result<Member, Error> {
+    let new_member = &NewMember {
+        product_id,
+        user_id,
+        role: EDITOR,
+        invited_by: Some(invited_by),
+        accepted_at: None,
+    };
+
+    diesel::insert_into(product_members::table)
+        .values(new_member)
+        .get_result::<Member>(conn)
+}
+
+pub fn accept(conn: &PgConnection, product_id: &i32, user_id: &i32) -> Result<Member, Error> {
+    diesel::update(
+        product_members::table
+            .filter(product_members::product_id.eq(product_id))
+            .filter(product_members::user_id.eq(user_id))
+            .filter(product_members::accepted_at.is_null()),
+    )
+    .set(product_members::accepted_at.eq(SystemTime::now()))
+    .get_result::<Member>(conn)
+}
+
+pub fn remove(conn: &PgConnection, product_id: &i32, user_id: &i32) -> Result<usize, Error> {
+    diesel::delete(
+        product_members::table
+            .filter(product_members::product_id.eq(product_id))
+            .filter(product_members::user_id.eq(user_id)),
+    )
+    .execute(conn)
+}
+
+pub fn find(conn: &PgConnection, product_id: &i32, user_id: &i32) -> Result<Member, Error> {
+    product_members::table
+        .filter(product_members::product_id.eq(product_id))
+        .filter(product_members::user_id.eq(user_id))
+        .first::<Member>(conn)
+}
+
+pub fn find_by_product_id(conn: &PgConnection, product_id: &i32) -> Result<Vec<Member>, Error> {
+    product_members::table
+        .filter(product_members::product_id.eq(product_id))
+        .order(product_members::id.asc())
+        .load::<Member>(conn)
+}
+
+/// Returns the membership of the user if it has been accepted.
+pub fn find_accepted(
+    conn: &PgConnection,
+    product_id: &i32,
+    user_id: &i32,
+) -> Result<Option<Member>, Error> {
+    find(conn, product_id, user_id)
+        .optional()
+        .map(|m| m.filter(|m| m.is_accepted()))
+}
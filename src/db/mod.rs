@@ -1,9 +1,22 @@
 use rocket_contrib::databases::diesel;
 
+pub mod bookmarks;
+pub mod collections;
+pub mod comments;
+pub mod devlogs;
+pub mod html;
+pub mod links;
+pub mod media;
+pub mod members;
+pub mod notifications;
 pub mod products;
 pub mod reactions;
+pub mod revisions;
 pub mod tags;
+pub mod trending;
 pub mod users;
+pub mod views;
+pub mod webhooks;
 
 #[database("diesel_postgres_pool")]
 pub struct Conn(diesel::PgConnection);
@@ -0,0 +1,86 @@
+use crate::models::notification::Notification;
+use crate::schema::notifications;
+use diesel::dsl::count_star;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::sql_types::{Integer, Nullable, Text, Uuid as UuidType};
+use std::time::SystemTime;
+use uuid::Uuid;
+
+/// Tells `user_id` that `actor_id` did something. While the user has not read
+/// it, events of the same kind and subject on the same product are folded into
+/// one notification, so that a burst of reactions notifies only once.
+pub fn notify(
+    conn: &PgConnection,
+    user_id: &i32,
+    kind: &str,
+    product_id: Option<&i32>,
+    subject: &str,
+    actor_id: &i32,
+) -> Result<(), Error> {
+    if user_id == actor_id {
+        return Ok(());
+    }
+    diesel::sql_query(
+        "INSERT INTO notifications (uuid, user_id, kind, product_id, subject, actor_ids) \
+         VALUES ($1, $2, $3, $4, $5, ARRAY[$6]) \
+         ON CONFLICT (user_id, kind, COALESCE(product_id, 0), subject) WHERE read_at IS NULL \
+         DO UPDATE SET actor_ids = array_prepend($6, array_remove(notifications.actor_ids, $6)), \
+         updated_at = CURRENT_TIMESTAMP",
+    )
+    .bind::<UuidType, _>(Uuid::new_v4())
+    .bind::<Integer, _>(user_id)
+    .bind::<Text, _>(kind)
+    .bind::<Nullable<Integer>, _>(product_id)
+    .bind::<Text, _>(subject)
+    .bind::<Integer, _>(actor_id)
+    .execute(conn)
+    .map(|_| ())
+}
+
+pub fn find(conn: &PgConnection, user_id: &i32, uuid: &Uuid) -> Result<Notification, Error> {
+    notifications::table
+        .filter(notifications::user_id.eq(user_id))
+        .filter(notifications::uuid.eq(uuid))
+        .first::<Notification>(conn)
+}
+
+/// Notifications of the user, the most recently updated first.
+pub fn find_by_user_id(
+    conn: &PgConnection,
+    user_id: &i32,
+    page: i64,
+    per_page: i64,
+) -> Result<Vec<Notification>, Error> {
+    notifications::table
+        .filter(notifications::user_id.eq(user_id))
+        .order((notifications::updated_at.desc(), notifications::id.desc()))
+        .limit(per_page)
+        .offset(page * per_page)
+        .load::<Notification>(conn)
+}
+
+pub fn unread_count(conn: &PgConnection, user_id: &i32) -> Result<i64, Error> {
+    notifications::table
+        .select(count_star())
+        .filter(notifications::user_id.eq(user_id))
+        .filter(notifications::read_at.is_null())
+        .first::<i64>(conn)
+}
+
+pub fn mark_read(conn: &PgConnection, id: &i32) -> Result<Notification, Error> {
+    diesel::update(notifications::table.find(id))
+        .set(notifications::read_at.eq(SystemTime::now()))
+        .get_result::<Notification>(conn)
+}
+
+/// Marks every unread notification of the user as read, returning how many there were.
+pub fn mark_all_read(conn: &PgConnection, user_id: &i32) -> Result<usize, Error> {
+    let unread = notifications::table
+        .filter(notifications::user_id.eq(user_id))
+        .filter(notifications::read_at.is_null());
+    diesel::update(unread)
+        .set(notifications::read_at.eq(SystemTime::now()))
+        .execute(conn)
+}
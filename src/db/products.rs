@@ -1,29 +1,99 @@
 use crate::db;
 use crate::error::TentechError;
-use crate::models::product::Product;
+use crate::markdown;
+use crate::models::product::{Product, PUBLISHED};
 use crate::models::tag::ProductTag;
-use crate::schema::{products, products_tags, tags};
+use crate::schema::{
+    product_members, product_slugs, products, products_tags, reaction_counts, reaction_kinds, tags,
+};
+use crate::slug::slugify;
+use diesel::dsl::sql;
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
-use diesel::result::{DatabaseErrorKind, Error};
+use diesel::query_dsl::GroupByDsl;
+use diesel::result::Error;
+use diesel::sql_types::{BigInt, Integer};
 use std::time::SystemTime;
 use uuid::Uuid;
 
 #[derive(Insertable, AsChangeset)]
 #[primary_key(uuid)]
+#[changeset_options(treat_none_as_null = "true")]
 #[table_name = "products"]
 pub struct NewProduct<'a> {
     pub title: &'a str,
     pub body: &'a str,
+    pub body_html: &'a str,
     pub simple: &'a str,
     pub img: &'a str,
     pub duration: &'a i32,
+    pub duration_from_devlogs: &'a bool,
     pub kind: &'a str,
     pub status: &'a str,
     pub user_id: &'a i32,
     pub uuid: &'a Uuid,
+    pub slug: &'a str,
+    pub inspired_by_id: Option<&'a i32>,
 }
 
+const POPULARITY: &str = "COALESCE(SUM(reaction_counts.count * reaction_kinds.weight), 0)::BIGINT";
+
+/// How many generations of inspiration are followed when building a lineage.
+pub const MAX_LINEAGE_DEPTH: i32 = 20;
+
+/// Listing order requested through the `sort` query parameter.
+pub enum Sort {
+    Created,
+    Updated,
+}
+
+impl Sort {
+    pub fn from_param(param: Option<String>) -> Sort {
+        match param.as_ref().map(String::as_str) {
+            Some("updated") => Sort::Updated,
+            _ => Sort::Created,
+        }
+    }
+}
+
+/// Picks a slug for `title` which no other product of the user uses or used.
+fn unique_slug(
+    conn: &PgConnection,
+    user_id: &i32,
+    title: &str,
+    uuid: &Uuid,
+    product_id: Option<i32>,
+) -> Result<String, Error> {
+    let base = slugify(title, uuid);
+    let mut slug = base.clone();
+    let mut suffix = 1;
+    loop {
+        let used = products::table
+            .select(products::id)
+            .filter(products::user_id.eq(user_id))
+            .filter(products::slug.eq(&slug))
+            .filter(products::uuid.ne(uuid))
+            .first::<i32>(conn)
+            .optional()?;
+        let mut used_before = product_slugs::table
+            .select(product_slugs::id)
+            .filter(product_slugs::user_id.eq(user_id))
+            .filter(product_slugs::slug.eq(&slug))
+            .into_boxed();
+        if let Some(product_id) = product_id {
+            used_before = used_before.filter(product_slugs::product_id.ne(product_id));
+        }
+        let used_before = used_before.first::<i32>(conn).optional()?;
+        if used.is_none() && used_before.is_none() {
+            return Ok(slug);
+        }
+        suffix += 1;
+        slug = format!("{}-{}", base, suffix);
+    }
+}
+
+/// Creates the product as a draft, or published right away when `publish` is
+/// set, so that a failure leaves nothing behind either way.
 pub fn create(
     conn: &PgConnection,
     title: &str,
@@ -31,30 +101,53 @@ pub fn create(
     simple: &str,
     img: &str,
     duration: &i32,
+    duration_from_devlogs: &bool,
     kind: &str,
     status: &str,
     tags: &Vec<i32>,
+    links: &Vec<(String, String)>,
+    inspired_by_id: Option<&i32>,
+    publish: &bool,
     user_id: &i32,
-) -> Result<Product, Error> {
-    let new_product = &NewProduct {
-        title,
-        body,
-        simple,
-        img,
-        duration,
-        kind,
-        status,
-        user_id,
-        uuid: &Uuid::new_v4(),
-    };
+) -> Result<Product, TentechError> {
+    let uuid = &Uuid::new_v4();
 
-    let product = diesel::insert_into(products::table)
-        .values(new_product)
-        .get_result::<Product>(conn)?;
-    db::tags::entry_to_product(conn, product.id, tags.to_vec());
-    Ok(product)
+    conn.transaction::<_, TentechError, _>(|| {
+        let tags = db::tags::validate_ids(conn, tags)?;
+        let slug = unique_slug(conn, user_id, title, uuid, None)?;
+        let new_product = &NewProduct {
+            title,
+            body,
+            body_html: &markdown::render(body),
+            simple,
+            img,
+            duration,
+            duration_from_devlogs,
+            kind,
+            status,
+            user_id,
+            uuid,
+            slug: &slug,
+            inspired_by_id,
+        };
+        let product = diesel::insert_into(products::table)
+            .values(new_product)
+            .get_result::<Product>(conn)?;
+        let product = db::devlogs::sync_duration(conn, &product.id)?.unwrap_or(product);
+        db::tags::entry_to_product(conn, product.id, tags.to_vec())?;
+        db::links::replace(conn, &product.id, links)?;
+        db::members::create_owner(conn, &product.id, user_id)?;
+        db::revisions::record(conn, &product, &tags, user_id)?;
+        if *publish {
+            return Ok(self::publish(conn, uuid)?);
+        }
+        Ok(product)
+    })
 }
 
+/// Updates the product on behalf of `user_id`, who may be an editor rather
+/// than the owner; the owner of the product is left untouched. Links are kept
+/// as they are when `links` is `None`.
 pub fn update(
     conn: &PgConnection,
     title: &str,
@@ -62,45 +155,131 @@ pub fn update(
     simple: &str,
     img: &str,
     duration: &i32,
+    duration_from_devlogs: &bool,
     kind: &str,
     status: &str,
     tags: &Vec<i32>,
+    links: Option<&Vec<(String, String)>>,
+    inspired_by_id: Option<&i32>,
     user_id: &i32,
     uuid: &Uuid,
-) -> Result<Product, Error> {
-    let new_product = &NewProduct {
-        title,
-        body,
-        simple,
-        img,
-        duration,
-        kind,
-        status,
-        user_id,
-        uuid,
-    };
-
-    let product = diesel::update(products::table.filter(products::uuid.eq(uuid)))
-        .set(new_product)
-        .get_result::<Product>(conn)?;
-    db::tags::delete_by_product_id(conn, product.id)?;
-    db::tags::entry_to_product(conn, product.id, tags.to_vec())?;
-    Ok(product)
+) -> Result<Product, TentechError> {
+    conn.transaction::<_, TentechError, _>(|| {
+        let tags = db::tags::validate_ids(conn, tags)?;
+        let current = find(conn, uuid)?;
+        let slug = if current.title == title {
+            current.slug.to_string()
+        } else {
+            unique_slug(conn, &current.user_id, title, uuid, Some(current.id))?
+        };
+        if slug != current.slug {
+            // Keep the old slug so that links to it can be redirected.
+            diesel::delete(
+                product_slugs::table
+                    .filter(product_slugs::product_id.eq(current.id))
+                    .filter(product_slugs::slug.eq(&slug)),
+            )
+            .execute(conn)?;
+            diesel::insert_into(product_slugs::table)
+                .values((
+                    product_slugs::product_id.eq(current.id),
+                    product_slugs::user_id.eq(current.user_id),
+                    product_slugs::slug.eq(&current.slug),
+                    product_slugs::created_at.eq(SystemTime::now()),
+                ))
+                .execute(conn)?;
+        }
+        let new_product = &NewProduct {
+            title,
+            body,
+            body_html: &markdown::render(body),
+            simple,
+            img,
+            duration,
+            duration_from_devlogs,
+            kind,
+            status,
+            user_id: &current.user_id,
+            uuid,
+            slug: &slug,
+            inspired_by_id,
+        };
+        let product = diesel::update(products::table.filter(products::uuid.eq(uuid)))
+            .set(new_product)
+            .get_result::<Product>(conn)?;
+        let product = db::devlogs::sync_duration(conn, &product.id)?.unwrap_or(product);
+        db::tags::delete_by_product_id(conn, product.id)?;
+        db::tags::entry_to_product(conn, product.id, tags.to_vec())?;
+        if let Some(links) = links {
+            db::links::replace(conn, &product.id, links)?;
+        }
+        db::revisions::record(conn, &product, &tags, user_id)?;
+        Ok(product)
+    })
 }
 
 pub fn find(conn: &PgConnection, id: &Uuid) -> Result<Product, Error> {
     products::table
         .filter(products::uuid.eq(id))
+        .filter(products::deleted_at.is_null())
+        .first::<Product>(conn)
+}
+
+pub fn find_by_slug(conn: &PgConnection, user_id: &i32, slug: &str) -> Result<Product, Error> {
+    products::table
+        .filter(products::user_id.eq(user_id))
+        .filter(products::slug.eq(slug))
+        .filter(products::deleted_at.is_null())
+        .first::<Product>(conn)
+}
+
+/// Finds the product which used `slug` before it was renamed.
+pub fn find_by_old_slug(conn: &PgConnection, user_id: &i32, slug: &str) -> Result<Product, Error> {
+    product_slugs::table
+        .inner_join(products::table)
+        .select(products::all_columns)
+        .filter(product_slugs::user_id.eq(user_id))
+        .filter(product_slugs::slug.eq(slug))
+        .filter(products::deleted_at.is_null())
         .first::<Product>(conn)
 }
 
 pub fn find_by_id(conn: &PgConnection, id: &i32) -> Result<Product, Error> {
-    products::table.find(id).first::<Product>(conn)
+    products::table
+        .find(id)
+        .filter(products::deleted_at.is_null())
+        .first::<Product>(conn)
 }
 
-pub fn find_by_user_id(conn: &PgConnection, id: &i32) -> Result<Vec<Product>, Error> {
+pub fn find_by_ids(conn: &PgConnection, ids: &Vec<i32>) -> Result<Vec<Product>, Error> {
     products::table
-        .filter(products::user_id.eq(id))
+        .filter(products::id.eq_any(ids))
+        .filter(products::deleted_at.is_null())
+        .load::<Product>(conn)
+}
+
+pub fn find_by_user_id(
+    conn: &PgConnection,
+    id: &i32,
+    include_unpublished: bool,
+    sort: &Sort,
+) -> Result<Vec<Product>, Error> {
+    let mut query = products::table
+        .inner_join(product_members::table)
+        .select(products::all_columns)
+        .filter(product_members::user_id.eq(id))
+        .filter(product_members::accepted_at.is_not_null())
+        .filter(products::deleted_at.is_null())
+        .into_boxed();
+    if !include_unpublished {
+        query = query.filter(products::state.eq(PUBLISHED));
+    }
+    query = match sort {
+        Sort::Created => query.order(products::created_at.desc()),
+        Sort::Updated => query.order(products::updated_at.desc()),
+    };
+    query
+        .then_order_by(products::id.desc())
         .load::<Product>(conn)
 }
 
@@ -112,6 +291,8 @@ pub fn find_by_tag_name(conn: &PgConnection, name: &String) -> Result<Vec<Produc
     products_tags::table
         .inner_join(products::table)
         .filter(products_tags::tag_id.eq(tag_id))
+        .filter(products::state.eq(PUBLISHED))
+        .filter(products::deleted_at.is_null())
         .load::<(ProductTag, Product)>(conn)
         .map(|p| {
             let new_p: Vec<_> = p.iter().map(|i| i.1.clone()).collect();
@@ -119,17 +300,176 @@ pub fn find_by_tag_name(conn: &PgConnection, name: &String) -> Result<Vec<Produc
         })
 }
 
-pub fn delete(conn: &PgConnection, id: &Uuid) -> Result<usize, Error> {
-    diesel::delete(products::table.filter(products::uuid.eq(id))).execute(conn)
+/// Moves the product to the trash of its owner.
+pub fn delete(conn: &PgConnection, id: &Uuid) -> Result<Product, Error> {
+    diesel::update(products::table.filter(products::uuid.eq(id)))
+        .set(products::deleted_at.eq(SystemTime::now()))
+        .get_result::<Product>(conn)
+}
+
+pub fn restore(conn: &PgConnection, id: &Uuid) -> Result<Product, Error> {
+    diesel::update(products::table.filter(products::uuid.eq(id)))
+        .set(products::deleted_at.eq(None::<SystemTime>))
+        .get_result::<Product>(conn)
 }
-pub fn recent(conn: &PgConnection) -> Result<Vec<Product>, Error> {
+
+/// Finds the product only while it is in the trash.
+pub fn find_deleted(conn: &PgConnection, id: &Uuid) -> Result<Product, Error> {
     products::table
-        .order(products::id.desc())
+        .filter(products::uuid.eq(id))
+        .filter(products::deleted_at.is_not_null())
+        .first::<Product>(conn)
+}
+
+/// Loads the trash of the user, most recently deleted first.
+pub fn find_deleted_by_user_id(conn: &PgConnection, user_id: &i32) -> Result<Vec<Product>, Error> {
+    products::table
+        .filter(products::user_id.eq(user_id))
+        .filter(products::deleted_at.is_not_null())
+        .order(products::deleted_at.desc())
+        .load::<Product>(conn)
+}
+
+/// Loads up to `limit` products which were deleted before `deleted_before`.
+pub fn find_expired(
+    conn: &PgConnection,
+    deleted_before: SystemTime,
+    limit: i64,
+) -> Result<Vec<Product>, Error> {
+    products::table
+        .filter(products::deleted_at.lt(deleted_before))
+        .order(products::deleted_at.asc())
+        .limit(limit)
+        .load::<Product>(conn)
+}
+
+/// Deletes the product for good, together with everything attached to it.
+pub fn purge(conn: &PgConnection, id: &i32) -> Result<usize, Error> {
+    diesel::delete(
+        products::table
+            .find(id)
+            .filter(products::deleted_at.is_not_null()),
+    )
+    .execute(conn)
+}
+
+pub fn set_state(conn: &PgConnection, id: &Uuid, state: &str) -> Result<Product, Error> {
+    diesel::update(products::table.filter(products::uuid.eq(id)))
+        .set(products::state.eq(state))
+        .get_result::<Product>(conn)
+}
+
+/// Publishes the product, stamping `published_at` only the first time.
+pub fn publish(conn: &PgConnection, id: &Uuid) -> Result<Product, Error> {
+    diesel::update(
+        products::table
+            .filter(products::uuid.eq(id))
+            .filter(products::published_at.is_null()),
+    )
+    .set(products::published_at.eq(SystemTime::now()))
+    .execute(conn)?;
+    set_state(conn, id, PUBLISHED)
+}
+
+pub fn recent(conn: &PgConnection, sort: &Sort) -> Result<Vec<Product>, Error> {
+    let query = products::table
+        .filter(products::state.eq(PUBLISHED))
+        .filter(products::deleted_at.is_null())
+        .into_boxed();
+    let query = match sort {
+        Sort::Created => query.order(products::created_at.desc()),
+        Sort::Updated => query.order(products::updated_at.desc()),
+    };
+    query
+        .then_order_by(products::id.desc())
         .load::<Product>(conn)
 }
-pub fn popular(conn: &PgConnection) -> Result<Vec<Product>, Error> {
+/// Loads the visible products which `id` was inspired by, the nearest first.
+pub fn ancestors(conn: &PgConnection, id: &i32) -> Result<Vec<Product>, Error> {
     diesel::sql_query(
-        "SELECT products.* FROM reactions INNER JOIN products on products.id = reactions.product_id GROUP BY product_id, products.id ORDER BY count(product_id) DESC",
+        "WITH RECURSIVE chain (id, inspired_by_id, depth) AS ( \
+           SELECT id, inspired_by_id, 0 FROM products WHERE id = $1 \
+           UNION ALL \
+           SELECT products.id, products.inspired_by_id, chain.depth + 1 FROM products \
+           INNER JOIN chain ON products.id = chain.inspired_by_id WHERE chain.depth < $2 \
+         ) \
+         SELECT products.* FROM chain INNER JOIN products ON products.id = chain.id \
+         WHERE chain.depth > 0 AND products.state = 'published' AND products.deleted_at IS NULL \
+         ORDER BY chain.depth",
     )
-        .load(conn)
+    .bind::<Integer, _>(id)
+    .bind::<Integer, _>(MAX_LINEAGE_DEPTH)
+    .load(conn)
+}
+
+/// Loads the visible products inspired by `id` or by any of them, nearest generations first.
+pub fn descendants(conn: &PgConnection, id: &i32) -> Result<Vec<Product>, Error> {
+    diesel::sql_query(
+        "WITH RECURSIVE tree (id, depth) AS ( \
+           SELECT id, 0 FROM products WHERE id = $1 \
+           UNION ALL \
+           SELECT products.id, tree.depth + 1 FROM products \
+           INNER JOIN tree ON products.inspired_by_id = tree.id WHERE tree.depth < $2 \
+         ) \
+         SELECT products.* FROM tree INNER JOIN products ON products.id = tree.id \
+         WHERE tree.depth > 0 AND products.state = 'published' AND products.deleted_at IS NULL \
+         ORDER BY tree.depth, products.created_at",
+    )
+    .bind::<Integer, _>(id)
+    .bind::<Integer, _>(MAX_LINEAGE_DEPTH)
+    .load(conn)
+}
+
+/// Checks whether `id` is `ancestor_id` or was inspired by it, visible or not.
+pub fn is_derived_from(conn: &PgConnection, id: &i32, ancestor_id: &i32) -> Result<bool, Error> {
+    let mut current = Some(*id);
+    let mut depth = 0;
+    while let Some(i) = current {
+        if i == *ancestor_id {
+            return Ok(true);
+        }
+        if depth > MAX_LINEAGE_DEPTH {
+            break;
+        }
+        current = products::table
+            .find(i)
+            .select(products::inspired_by_id)
+            .first::<Option<i32>>(conn)?;
+        depth += 1;
+    }
+    Ok(false)
+}
+
+/// Loads the visible products directly inspired by `id`, latest first.
+pub fn find_derived(conn: &PgConnection, id: &i32) -> Result<Vec<Product>, Error> {
+    products::table
+        .filter(products::inspired_by_id.eq(id))
+        .filter(products::state.eq(PUBLISHED))
+        .filter(products::deleted_at.is_null())
+        .order((products::created_at.desc(), products::id.desc()))
+        .load::<Product>(conn)
+}
+
+/// Ranks the published products by their reactions, each weighted by the
+/// `weight` of its kind. Products without reactions come last, and ties are
+/// broken by the latest publication.
+pub fn popular(
+    conn: &PgConnection,
+    page: i64,
+    per_page: i64,
+) -> Result<Vec<(Product, i64)>, Error> {
+    products::table
+        .left_join(reaction_counts::table.inner_join(reaction_kinds::table))
+        .select((products::all_columns, sql::<BigInt>(POPULARITY)))
+        .filter(products::state.eq(PUBLISHED))
+        .filter(products::deleted_at.is_null())
+        .group_by(products::id)
+        .order((
+            sql::<BigInt>(POPULARITY).desc(),
+            products::published_at.desc(),
+            products::id.desc(),
+        ))
+        .limit(per_page)
+        .offset(page * per_page)
+        .load::<(Product, i64)>(conn)
 }
@@ -1,98 +1,199 @@
 use crate::db;
 use crate::error::TentechError;
-use crate::models::product::Product;
-use crate::models::reaction::Reaction;
+use crate::models::product::{Product, PUBLISHED};
+use crate::models::reaction::{Reaction, ReactionKind};
 use crate::models::user::User;
 use crate::routes::reactions::NewReaction;
 use crate::schema::*;
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
 use diesel::result::{DatabaseErrorKind, Error};
+use diesel::sql_types::{Integer, Text};
+use std::collections::HashMap;
 use std::time::SystemTime;
 use std::vec::Vec;
 
+/// Adds a reaction unless the user already reached the limit of its kind.
+///
+/// The limit is checked and the count raised by a single upsert on the
+/// user's counter row, so concurrent requests cannot both slip under it.
 pub fn add_react(
     conn: &PgConnection,
     reaction: &NewReaction,
     product_id: &i32,
     user_id: &i32,
 ) -> Result<(), TentechError> {
-    let current_count = reactions::table
-        .filter(reactions::product_id.eq(product_id))
-        .filter(reactions::user_id.eq(user_id))
-        .filter(reactions::kind.eq(reaction.kind.to_string()))
-        .select(reactions::id)
-        .load::<i32>(conn)
-        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))?
-        .len() as i32;
-    if current_count > 4 {
-        return Err(TentechError::CannotReactTooMany);
-    }
-    diesel::insert_into(reactions::table)
-        .values((
-            reactions::kind.eq(reaction.kind.to_string()),
-            reactions::product_id.eq(product_id),
-            reactions::user_id.eq(user_id),
-            reactions::created_at.eq(SystemTime::now()),
-        ))
-        .execute(conn)
-        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))?;
-    Ok(())
+    conn.transaction::<_, TentechError, _>(|| {
+        let kind = find_kind(conn, &reaction.kind)?;
+        if kind.max_per_user < 1 {
+            return Err(TentechError::CannotReactTooMany);
+        }
+        // No row is touched once the limit is reached.
+        let counted = diesel::sql_query(
+            "INSERT INTO user_reaction_counts (user_id, product_id, kind, count) \
+             VALUES ($1, $2, $3, 1) \
+             ON CONFLICT (user_id, product_id, kind) DO UPDATE \
+             SET count = user_reaction_counts.count + 1 \
+             WHERE user_reaction_counts.count < $4",
+        )
+        .bind::<Integer, _>(user_id)
+        .bind::<Integer, _>(product_id)
+        .bind::<Text, _>(&kind.name)
+        .bind::<Integer, _>(kind.max_per_user)
+        .execute(conn)?;
+        if counted == 0 {
+            return Err(TentechError::CannotReactTooMany);
+        }
+        diesel::insert_into(reactions::table)
+            .values((
+                reactions::kind.eq(&kind.name),
+                reactions::product_id.eq(product_id),
+                reactions::user_id.eq(user_id),
+                reactions::created_at.eq(SystemTime::now()),
+            ))
+            .execute(conn)?;
+        Ok(())
+    })
 }
 
+/// Removes the latest reaction of the kind given by the user.
 pub fn sub_react(
     conn: &PgConnection,
     reaction: &NewReaction,
     product_id: &i32,
     user_id: &i32,
 ) -> Result<(), TentechError> {
-    let current_count = reactions::table
-        .filter(reactions::product_id.eq(product_id))
-        .filter(reactions::user_id.eq(user_id))
-        .filter(reactions::kind.eq(reaction.kind.to_string()))
-        .select(reactions::id)
-        .load::<i32>(conn)
-        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))?
-        .len() as i32;
-    if current_count < 1 {
-        return Err(TentechError::CannotReactTooMany);
+    conn.transaction::<_, TentechError, _>(|| {
+        let counter = user_reaction_counts::table
+            .filter(user_reaction_counts::user_id.eq(user_id))
+            .filter(user_reaction_counts::product_id.eq(product_id))
+            .filter(user_reaction_counts::kind.eq(&reaction.kind))
+            .filter(user_reaction_counts::count.gt(0));
+        let lowered = diesel::update(counter)
+            .set(user_reaction_counts::count.eq(user_reaction_counts::count - 1))
+            .execute(conn)?;
+        if lowered == 0 {
+            return Err(TentechError::NoReactionToRemove);
+        }
+        let delete_id = reactions::table
+            .select(reactions::id)
+            .filter(reactions::product_id.eq(product_id))
+            .filter(reactions::user_id.eq(user_id))
+            .filter(reactions::kind.eq(&reaction.kind))
+            .order(reactions::id.desc())
+            .first::<i32>(conn)?;
+        diesel::delete(reactions::table.find(delete_id)).execute(conn)?;
+        Ok(())
+    })
+}
+
+/// Reaction counts keyed by product id and then by kind.
+pub type Counts = HashMap<i32, HashMap<String, i64>>;
+
+fn group_counts(rows: Vec<(i32, String, i64)>) -> Counts {
+    let mut counts: Counts = HashMap::new();
+    for (product_id, kind, count) in rows {
+        counts.entry(product_id).or_default().insert(kind, count);
     }
-    let delete_id = reactions::table
-        .select(reactions::id)
-        .filter(reactions::product_id.eq(product_id))
-        .filter(reactions::user_id.eq(user_id))
-        .filter(reactions::kind.eq(reaction.kind.to_string()))
-        .order(reactions::id.desc())
-        .first::<i32>(conn)
-        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))?;
-    diesel::delete(reactions::table.find(delete_id))
-        .execute(conn)
-        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))?;
-    Ok(())
+    counts
 }
 
-pub fn get_by_product_id(conn: &PgConnection, product_id: &i32) -> Result<Vec<Reaction>, Error> {
-    reactions::table
-        .filter(reactions::product_id.eq(product_id))
-        .load::<Reaction>(conn)
+/// Reads the counts maintained by the `count_reactions` trigger.
+pub fn count_by_product_ids(conn: &PgConnection, product_ids: &Vec<i32>) -> Result<Counts, Error> {
+    reaction_counts::table
+        .filter(reaction_counts::product_id.eq_any(product_ids))
+        .filter(reaction_counts::count.gt(0))
+        .load::<(i32, String, i64)>(conn)
+        .map(group_counts)
 }
 
-pub fn get_by_user_id(
+/// Counts the reactions the user gave to each of the products.
+pub fn count_by_user_id(
     conn: &PgConnection,
+    product_ids: &Vec<i32>,
     user_id: &i32,
-) -> Result<Vec<(Product, Reaction, User)>, Error> {
-    let resources: Vec<_> = users::table
-        .inner_join(products::table.inner_join(reactions::table))
-        .filter(users::id.eq(user_id))
-        .order(reactions::created_at.desc())
-        .limit(20)
-        .load::<(User, (Product, Reaction))>(conn)?;
-    let new_resources: Vec<(Product, Reaction, User)> = resources
-        .iter()
-        .map(|r| {
-            let by = db::users::find(conn, &(&r.1).1.user_id).unwrap();
-            ((r.1).0.clone(), (r.1).1.clone(), by)
+) -> Result<Counts, Error> {
+    user_reaction_counts::table
+        .select((
+            user_reaction_counts::product_id,
+            user_reaction_counts::kind,
+            user_reaction_counts::count,
+        ))
+        .filter(user_reaction_counts::user_id.eq(user_id))
+        .filter(user_reaction_counts::product_id.eq_any(product_ids))
+        .filter(user_reaction_counts::count.gt(0))
+        .load::<(i32, String, i32)>(conn)
+        .map(|rows| {
+            group_counts(
+                rows.into_iter()
+                    .map(|(product_id, kind, count)| (product_id, kind, count as i64))
+                    .collect(),
+            )
+        })
+}
+
+/// Attaches the reacting user to every reaction, looking them up at once.
+fn with_users(
+    conn: &PgConnection,
+    resources: Vec<(Reaction, Product)>,
+) -> Result<Vec<(Reaction, Product, User)>, Error> {
+    let user_ids: Vec<i32> = resources.iter().map(|(r, _)| r.user_id).collect();
+    let users = db::users::find_by_ids(conn, &user_ids)?;
+    Ok(resources
+        .into_iter()
+        .filter_map(|(r, p)| {
+            let by = users.iter().find(|u| u.id == r.user_id)?.clone();
+            Some((r, p, by))
         })
-        .collect();
-    Ok(new_resources)
+        .collect())
+}
+
+/// Reactions other users left on the published products owned by the user,
+/// latest first.
+pub fn received_by_user_id(
+    conn: &PgConnection,
+    user_id: &i32,
+    page: i64,
+    per_page: i64,
+) -> Result<Vec<(Reaction, Product, User)>, Error> {
+    let resources = reactions::table
+        .inner_join(products::table)
+        .filter(products::user_id.eq(user_id))
+        .filter(reactions::user_id.ne(user_id))
+        .filter(products::state.eq(PUBLISHED))
+        .filter(products::deleted_at.is_null())
+        .order((reactions::created_at.desc(), reactions::id.desc()))
+        .limit(per_page)
+        .offset(page * per_page)
+        .load::<(Reaction, Product)>(conn)?;
+    with_users(conn, resources)
+}
+
+/// Reactions the user has given to published products, latest first.
+pub fn given_by_user_id(
+    conn: &PgConnection,
+    user_id: &i32,
+    page: i64,
+    per_page: i64,
+) -> Result<Vec<(Reaction, Product, User)>, Error> {
+    let resources = reactions::table
+        .inner_join(products::table)
+        .filter(reactions::user_id.eq(user_id))
+        .filter(products::state.eq(PUBLISHED))
+        .filter(products::deleted_at.is_null())
+        .order((reactions::created_at.desc(), reactions::id.desc()))
+        .limit(per_page)
+        .offset(page * per_page)
+        .load::<(Reaction, Product)>(conn)?;
+    with_users(conn, resources)
+}
+
+pub fn kinds(conn: &PgConnection) -> Result<Vec<ReactionKind>, Error> {
+    reaction_kinds::table
+        .order(reaction_kinds::position.asc())
+        .load::<ReactionKind>(conn)
+}
+
+pub fn find_kind(conn: &PgConnection, name: &str) -> Result<ReactionKind, Error> {
+    reaction_kinds::table.find(name).first::<ReactionKind>(conn)
 }
@@ -0,0 +1,69 @@
+use crate::models::product::Product;
+use crate::models::revision::Revision;
+use crate::schema::product_revisions;
+use diesel::dsl::max;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+use std::time::SystemTime;
+
+#[derive(Insertable)]
+#[table_name = "product_revisions"]
+pub struct NewRevision<'a> {
+    pub product_id: &'a i32,
+    pub number: &'a i32,
+    pub user_id: &'a i32,
+    pub title: &'a str,
+    pub body: &'a str,
+    pub simple: &'a str,
+    pub img: &'a str,
+    pub kind: &'a str,
+    pub status: &'a str,
+    pub duration: &'a i32,
+    pub tag_ids: &'a Vec<i32>,
+    pub created_at: &'a SystemTime,
+}
+
+/// Stores the current contents of `product` as its next revision.
+pub fn record(
+    conn: &PgConnection,
+    product: &Product,
+    tag_ids: &Vec<i32>,
+    user_id: &i32,
+) -> Result<Revision, Error> {
+    let latest = product_revisions::table
+        .filter(product_revisions::product_id.eq(product.id))
+        .select(max(product_revisions::number))
+        .first::<Option<i32>>(conn)?;
+    let new_revision = &NewRevision {
+        product_id: &product.id,
+        number: &(latest.unwrap_or(0) + 1),
+        user_id,
+        title: &product.title,
+        body: &product.body,
+        simple: &product.simple,
+        img: &product.img,
+        kind: &product.kind,
+        status: &product.status,
+        duration: &product.duration,
+        tag_ids,
+        created_at: &SystemTime::now(),
+    };
+    diesel::insert_into(product_revisions::table)
+        .values(new_revision)
+        .get_result::<Revision>(conn)
+}
+
+pub fn find_by_product_id(conn: &PgConnection, product_id: &i32) -> Result<Vec<Revision>, Error> {
+    product_revisions::table
+        .filter(product_revisions::product_id.eq(product_id))
+        .order(product_revisions::number.desc())
+        .load::<Revision>(conn)
+}
+
+pub fn find(conn: &PgConnection, product_id: &i32, number: &i32) -> Result<Revision, Error> {
+    product_revisions::table
+        .filter(product_revisions::product_id.eq(product_id))
+        .filter(product_revisions::number.eq(number))
+        .first::<Revision>(conn)
+}
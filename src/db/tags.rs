@@ -3,6 +3,7 @@ use crate::models::tag::ProductTag;
 use crate::models::tag::Tag;
 use crate::schema::products_tags;
 use crate::schema::tags;
+use crate::validation::field_error;
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
 use diesel::result::{DatabaseErrorKind, Error};
@@ -13,6 +14,8 @@ use std::time::SystemTime;
 use std::vec::Vec;
 use uuid::Uuid;
 
+pub const MAX_TAGS_PER_PRODUCT: usize = 10;
+
 #[derive(Insertable, Debug)]
 #[table_name = "tags"]
 pub struct NewTag {
@@ -73,6 +76,40 @@ pub fn get_by_product_id(conn: &PgConnection, product_id: &i32) -> Result<Vec<i3
         .load::<i32>(conn)
 }
 
+/// Dedupes `tags` and checks that every id exists, keeping the given order.
+pub fn validate_ids(conn: &PgConnection, tags: &Vec<i32>) -> Result<Vec<i32>, TentechError> {
+    let mut ids: Vec<i32> = Vec::new();
+    for id in tags {
+        if !ids.contains(id) {
+            ids.push(*id);
+        }
+    }
+    if ids.len() > MAX_TAGS_PER_PRODUCT {
+        return Err(field_error(
+            "tags",
+            "too_many_tags",
+            format!("A product can have at most {} tags", MAX_TAGS_PER_PRODUCT),
+        ));
+    }
+    let exist_ids = tags::table
+        .select(tags::id)
+        .filter(tags::id.eq_any(&ids))
+        .load::<i32>(conn)?;
+    let unknown_ids: Vec<String> = ids
+        .iter()
+        .filter(|id| !exist_ids.contains(id))
+        .map(|id| id.to_string())
+        .collect();
+    if !unknown_ids.is_empty() {
+        return Err(field_error(
+            "tags",
+            "unknown_tag",
+            format!("Unknown tag ids: {}", unknown_ids.join(", ")),
+        ));
+    }
+    Ok(ids)
+}
+
 pub fn entry_to_product(
     conn: &PgConnection,
     product_id: i32,
@@ -0,0 +1,103 @@
+use crate::models::product::{Product, PUBLISHED};
+use crate::schema::{products, trending_scores};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::sql_types::{Text, Timestamp};
+use std::time::{Duration, SystemTime};
+
+/// Period over which reactions are counted, requested through the `window`
+/// query parameter.
+pub enum Window {
+    Day,
+    Week,
+    Month,
+    All,
+}
+
+pub const WINDOWS: [Window; 4] = [Window::Day, Window::Week, Window::Month, Window::All];
+
+impl Window {
+    pub fn from_param(param: Option<String>) -> Window {
+        match param.as_ref().map(String::as_str) {
+            Some("24h") => Window::Day,
+            Some("30d") => Window::Month,
+            Some("all") => Window::All,
+            _ => Window::Week,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Window::Day => "24h",
+            Window::Week => "7d",
+            Window::Month => "30d",
+            Window::All => "all",
+        }
+    }
+
+    fn length(&self) -> Option<Duration> {
+        let day = 24 * 60 * 60;
+        match self {
+            Window::Day => Some(Duration::from_secs(day)),
+            Window::Week => Some(Duration::from_secs(7 * day)),
+            Window::Month => Some(Duration::from_secs(30 * day)),
+            Window::All => None,
+        }
+    }
+}
+
+/// Recomputes the scores of the window: the reactions given to each published
+/// product within it, weighted by their kind like the popular listing.
+pub fn refresh(conn: &PgConnection, window: &Window) -> Result<usize, Error> {
+    conn.transaction(|| {
+        diesel::delete(trending_scores::table.filter(trending_scores::period.eq(window.name())))
+            .execute(conn)?;
+        match window.length() {
+            Some(length) => diesel::sql_query(
+                "INSERT INTO trending_scores (product_id, period, score, refreshed_at) \
+                 SELECT reactions.product_id, $1, SUM(reaction_kinds.weight)::BIGINT, NOW() \
+                 FROM reactions \
+                 INNER JOIN reaction_kinds ON reaction_kinds.name = reactions.kind \
+                 INNER JOIN products ON products.id = reactions.product_id \
+                 WHERE reactions.created_at > $2 \
+                 AND products.state = 'published' AND products.deleted_at IS NULL \
+                 GROUP BY reactions.product_id HAVING SUM(reaction_kinds.weight) > 0",
+            )
+            .bind::<Text, _>(window.name())
+            .bind::<Timestamp, _>(SystemTime::now() - length)
+            .execute(conn),
+            // The all-time ranking reads the counters instead of every reaction.
+            None => diesel::sql_query(
+                "INSERT INTO trending_scores (product_id, period, score, refreshed_at) \
+                 SELECT reaction_counts.product_id, $1, \
+                 SUM(reaction_counts.count * reaction_kinds.weight)::BIGINT, NOW() \
+                 FROM reaction_counts \
+                 INNER JOIN reaction_kinds ON reaction_kinds.name = reaction_counts.kind \
+                 INNER JOIN products ON products.id = reaction_counts.product_id \
+                 WHERE products.state = 'published' AND products.deleted_at IS NULL \
+                 GROUP BY reaction_counts.product_id \
+                 HAVING SUM(reaction_counts.count * reaction_kinds.weight) > 0",
+            )
+            .bind::<Text, _>(window.name())
+            .execute(conn),
+        }
+    })
+}
+
+/// Loads the highest scoring products of the window from the last refresh.
+pub fn find(conn: &PgConnection, window: &Window, limit: i64) -> Result<Vec<Product>, Error> {
+    trending_scores::table
+        .inner_join(products::table)
+        .select(products::all_columns)
+        .filter(trending_scores::period.eq(window.name()))
+        .filter(products::state.eq(PUBLISHED))
+        .filter(products::deleted_at.is_null())
+        .order((
+            trending_scores::score.desc(),
+            products::published_at.desc(),
+            products::id.desc(),
+        ))
+        .limit(limit)
+        .load::<Product>(conn)
+}
@@ -67,19 +67,25 @@ pub fn delete_all(conn: &PgConnection) -> Result<usize, Error> {
     diesel::delete(users::table).execute(conn)
 }
 
-pub fn activate(conn: &PgConnection, target: &User) -> Result<usize, Error> {
+pub fn activate(conn: &PgConnection, target: &User) -> Result<User, Error> {
     diesel::update(target)
         .set((
             users::activated.eq(true),
             users::activated_at.eq(SystemTime::now()),
         ))
-        .execute(conn)
+        .get_result::<User>(conn)
 }
 
 pub fn find(conn: &PgConnection, id: &i32) -> Result<User, Error> {
     users::table.find(id).first::<User>(conn)
 }
 
+pub fn find_by_ids(conn: &PgConnection, ids: &Vec<i32>) -> Result<Vec<User>, Error> {
+    users::table
+        .filter(users::id.eq_any(ids))
+        .load::<User>(conn)
+}
+
 pub fn find_by_username(conn: &PgConnection, name: &String) -> Result<User, Error> {
     users::table
         .filter(users::username.eq(name))
@@ -0,0 +1,93 @@
+use crate::schema::product_views;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::sql_types::{BigInt, Integer, Nullable, Text};
+use serde::Serialize;
+
+#[derive(Debug, QueryableByName, Serialize)]
+pub struct DailyViews {
+    #[sql_type = "Text"]
+    pub day: String,
+    #[sql_type = "BigInt"]
+    pub views: i64,
+}
+
+#[derive(Debug, QueryableByName, Serialize)]
+pub struct DailyReactions {
+    #[sql_type = "Text"]
+    pub day: String,
+    #[sql_type = "Text"]
+    pub kind: String,
+    #[sql_type = "BigInt"]
+    pub count: i64,
+}
+
+#[derive(Debug, QueryableByName, Serialize)]
+pub struct ReferrerViews {
+    #[sql_type = "Nullable<Text>"]
+    pub referrer: Option<String>,
+    #[sql_type = "BigInt"]
+    pub views: i64,
+}
+
+/// Records a view unless the viewer has already been counted today.
+pub fn record(
+    conn: &PgConnection,
+    product_id: &i32,
+    viewer: &str,
+    referrer: Option<&str>,
+) -> Result<usize, Error> {
+    diesel::insert_into(product_views::table)
+        .values((
+            product_views::product_id.eq(product_id),
+            product_views::viewer.eq(viewer),
+            product_views::referrer.eq(referrer),
+        ))
+        .on_conflict_do_nothing()
+        .execute(conn)
+}
+
+/// Counts the views of each of the last `days` days, including days without any.
+pub fn daily(conn: &PgConnection, product_id: &i32, days: &i32) -> Result<Vec<DailyViews>, Error> {
+    diesel::sql_query(
+        "SELECT to_char(d.day, 'YYYY-MM-DD') AS day, COUNT(product_views.id) AS views \
+         FROM generate_series(CURRENT_DATE - ($2 - 1), CURRENT_DATE, '1 day') AS d(day) \
+         LEFT JOIN product_views ON product_views.day = d.day AND product_views.product_id = $1 \
+         GROUP BY d.day ORDER BY d.day",
+    )
+    .bind::<Integer, _>(product_id)
+    .bind::<Integer, _>(days)
+    .load::<DailyViews>(conn)
+}
+
+pub fn reactions_daily(
+    conn: &PgConnection,
+    product_id: &i32,
+    days: &i32,
+) -> Result<Vec<DailyReactions>, Error> {
+    diesel::sql_query(
+        "SELECT to_char(created_at::date, 'YYYY-MM-DD') AS day, kind, COUNT(*) AS count \
+         FROM reactions WHERE product_id = $1 AND created_at::date > CURRENT_DATE - $2 \
+         GROUP BY created_at::date, kind ORDER BY created_at::date, kind",
+    )
+    .bind::<Integer, _>(product_id)
+    .bind::<Integer, _>(days)
+    .load::<DailyReactions>(conn)
+}
+
+/// Counts the views by referring host; views without a referrer are counted under `None`.
+pub fn referrers(
+    conn: &PgConnection,
+    product_id: &i32,
+    days: &i32,
+) -> Result<Vec<ReferrerViews>, Error> {
+    diesel::sql_query(
+        "SELECT referrer, COUNT(*) AS views FROM product_views \
+         WHERE product_id = $1 AND day > CURRENT_DATE - $2 \
+         GROUP BY referrer ORDER BY views DESC, referrer",
+    )
+    .bind::<Integer, _>(product_id)
+    .bind::<Integer, _>(days)
+    .load::<ReferrerViews>(conn)
+}
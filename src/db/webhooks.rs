@@ -0,0 +1,123 @@
+use crate::models::webhook::{Delivery, Webhook};
+use crate::schema::{webhook_deliveries, webhooks};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+use std::time::SystemTime;
+use uuid::Uuid;
+
+pub fn create(
+    conn: &PgConnection,
+    user_id: &i32,
+    url: &str,
+    secret: &str,
+    events: &Vec<String>,
+    tag_ids: &Vec<i32>,
+) -> Result<Webhook, Error> {
+    diesel::insert_into(webhooks::table)
+        .values((
+            webhooks::uuid.eq(Uuid::new_v4()),
+            webhooks::user_id.eq(user_id),
+            webhooks::url.eq(url),
+            webhooks::secret.eq(secret),
+            webhooks::events.eq(events),
+            webhooks::tag_ids.eq(tag_ids),
+        ))
+        .get_result::<Webhook>(conn)
+}
+
+pub fn find(conn: &PgConnection, user_id: &i32, uuid: &Uuid) -> Result<Webhook, Error> {
+    webhooks::table
+        .filter(webhooks::user_id.eq(user_id))
+        .filter(webhooks::uuid.eq(uuid))
+        .first::<Webhook>(conn)
+}
+
+pub fn find_by_user_id(conn: &PgConnection, user_id: &i32) -> Result<Vec<Webhook>, Error> {
+    webhooks::table
+        .filter(webhooks::user_id.eq(user_id))
+        .order(webhooks::id.asc())
+        .load::<Webhook>(conn)
+}
+
+/// Webhooks subscribed to the event, whatever their tags.
+pub fn find_by_event(conn: &PgConnection, event: &str) -> Result<Vec<Webhook>, Error> {
+    webhooks::table
+        .filter(webhooks::events.contains(vec![event.to_string()]))
+        .load::<Webhook>(conn)
+}
+
+pub fn delete(conn: &PgConnection, id: &i32) -> Result<usize, Error> {
+    diesel::delete(webhooks::table.find(id)).execute(conn)
+}
+
+/// Queues the payload to be sent to the webhook right away.
+pub fn enqueue(
+    conn: &PgConnection,
+    webhook_id: &i32,
+    event: &str,
+    payload: &str,
+) -> Result<Delivery, Error> {
+    diesel::insert_into(webhook_deliveries::table)
+        .values((
+            webhook_deliveries::uuid.eq(Uuid::new_v4()),
+            webhook_deliveries::webhook_id.eq(webhook_id),
+            webhook_deliveries::event.eq(event),
+            webhook_deliveries::payload.eq(payload),
+            webhook_deliveries::next_attempt_at.eq(SystemTime::now()),
+        ))
+        .get_result::<Delivery>(conn)
+}
+
+/// Loads up to `limit` deliveries whose next attempt is due by `now`, the
+/// longest waiting first.
+pub fn find_due(
+    conn: &PgConnection,
+    now: SystemTime,
+    limit: i64,
+) -> Result<Vec<(Delivery, Webhook)>, Error> {
+    webhook_deliveries::table
+        .inner_join(webhooks::table)
+        .filter(webhook_deliveries::next_attempt_at.le(now))
+        .order(webhook_deliveries::next_attempt_at.asc())
+        .limit(limit)
+        .load::<(Delivery, Webhook)>(conn)
+}
+
+/// Records an attempt; `next_attempt_at` is `None` once delivered or given up on.
+pub fn record_attempt(
+    conn: &PgConnection,
+    id: &i32,
+    status: Option<i32>,
+    error: Option<String>,
+    next_attempt_at: Option<SystemTime>,
+    delivered_at: Option<SystemTime>,
+) -> Result<Delivery, Error> {
+    diesel::update(webhook_deliveries::table.find(id))
+        .set((
+            webhook_deliveries::attempts.eq(webhook_deliveries::attempts + 1),
+            webhook_deliveries::status.eq(status),
+            webhook_deliveries::error.eq(error),
+            webhook_deliveries::next_attempt_at.eq(next_attempt_at),
+            webhook_deliveries::delivered_at.eq(delivered_at),
+        ))
+        .get_result::<Delivery>(conn)
+}
+
+/// Deliveries of the webhook, the latest first.
+pub fn find_deliveries(
+    conn: &PgConnection,
+    webhook_id: &i32,
+    page: i64,
+    per_page: i64,
+) -> Result<Vec<Delivery>, Error> {
+    webhook_deliveries::table
+        .filter(webhook_deliveries::webhook_id.eq(webhook_id))
+        .order((
+            webhook_deliveries::created_at.desc(),
+            webhook_deliveries::id.desc(),
+        ))
+        .limit(per_page)
+        .offset(page * per_page)
+        .load::<Delivery>(conn)
+}
@@ -1,3 +1,4 @@
+use diesel::result::Error as DieselError;
 use rocket::http::ContentType;
 use rocket::http::Status;
 use rocket::response::Responder;
@@ -27,9 +28,17 @@ pub enum TentechError {
     CannotDecodeBase64,
 
     CannotPutS3Object,
+    CannotDeleteS3Object,
     TooLargeObject,
 
     CannotReactTooMany,
+    CannotReactToOwn,
+    NoReactionToRemove,
+
+    NotFound(String),
+    InvalidTransition(String),
+    AlreadyMember,
+    TooManyStreams,
 }
 
 #[derive(Debug, Serialize)]
@@ -81,6 +90,10 @@ impl Into<ErrorJson> for TentechError {
                 r#type: "CannotPutS3Object".to_string(),
                 message: format!("{}", self),
             },
+            TentechError::CannotDeleteS3Object => ErrorJson {
+                r#type: "CannotDeleteS3Object".to_string(),
+                message: format!("{}", self),
+            },
             TentechError::TooLargeObject => ErrorJson {
                 r#type: "TooLargeObject".to_string(),
                 message: format!("{}", self),
@@ -89,6 +102,30 @@ impl Into<ErrorJson> for TentechError {
                 r#type: "CannotReactTooMany".to_string(),
                 message: format!("{}", self),
             },
+            TentechError::CannotReactToOwn => ErrorJson {
+                r#type: "CannotReactToOwn".to_string(),
+                message: format!("{}", self),
+            },
+            TentechError::NoReactionToRemove => ErrorJson {
+                r#type: "NoReactionToRemove".to_string(),
+                message: format!("{}", self),
+            },
+            TentechError::NotFound(ref m) => ErrorJson {
+                r#type: "NotFound".to_string(),
+                message: format!("{}", self),
+            },
+            TentechError::InvalidTransition(ref m) => ErrorJson {
+                r#type: "InvalidTransition".to_string(),
+                message: format!("{}", self),
+            },
+            TentechError::AlreadyMember => ErrorJson {
+                r#type: "AlreadyMember".to_string(),
+                message: format!("{}", self),
+            },
+            TentechError::TooManyStreams => ErrorJson {
+                r#type: "TooManyStreams".to_string(),
+                message: format!("{}", self),
+            },
         }
     }
 }
@@ -106,8 +143,15 @@ impl fmt::Display for TentechError {
             TentechError::Unauthorized(ref m) => f.write_str(m),
             TentechError::CannotDecodeBase64 => f.write_str("Cannot decode base64"),
             TentechError::CannotPutS3Object => f.write_str("Cannot put object to s3"),
+            TentechError::CannotDeleteS3Object => f.write_str("Cannot delete object from s3"),
             TentechError::TooLargeObject => f.write_str("object is too large"),
             TentechError::CannotReactTooMany => f.write_str("Cannot react too many"),
+            TentechError::CannotReactToOwn => f.write_str("Cannot react to own product"),
+            TentechError::NoReactionToRemove => f.write_str("No reaction to remove"),
+            TentechError::NotFound(ref m) => f.write_str(m),
+            TentechError::InvalidTransition(ref m) => f.write_str(m),
+            TentechError::AlreadyMember => f.write_str("Already a member of the product"),
+            TentechError::TooManyStreams => f.write_str("Too many event streams are open"),
         }
     }
 }
@@ -121,6 +165,15 @@ impl Error for TentechError {
     }
 }
 
+impl From<DieselError> for TentechError {
+    fn from(err: DieselError) -> TentechError {
+        match err {
+            DieselError::NotFound => TentechError::NotFound(format!("{}", err)),
+            _ => TentechError::DatabaseFailed(format!("{}", err)),
+        }
+    }
+}
+
 impl Responder<'static> for TentechError {
     fn respond_to(self, _: &Request) -> Result<Response<'static>, Status> {
         let status = match self {
@@ -134,8 +187,15 @@ impl Responder<'static> for TentechError {
             TentechError::Unauthorized(_) => Status::Unauthorized,
             TentechError::CannotDecodeBase64 => Status::BadRequest,
             TentechError::CannotPutS3Object => Status::UnprocessableEntity,
+            TentechError::CannotDeleteS3Object => Status::UnprocessableEntity,
             TentechError::TooLargeObject => Status::BadRequest,
             TentechError::CannotReactTooMany => Status::BadRequest,
+            TentechError::CannotReactToOwn => Status::Forbidden,
+            TentechError::NoReactionToRemove => Status::BadRequest,
+            TentechError::NotFound(_) => Status::NotFound,
+            TentechError::InvalidTransition(_) => Status::Conflict,
+            TentechError::AlreadyMember => Status::Conflict,
+            TentechError::TooManyStreams => Status::ServiceUnavailable,
         };
         let error: ErrorJson = self.into();
         Response::build()
@@ -0,0 +1,203 @@
+use serde_json::Value;
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// How many events a client may lag behind before it is dropped.
+const BACKLOG: usize = 64;
+
+/// How long a stream stays silent before a comment is sent to keep it open.
+const KEEPALIVE: Duration = Duration::from_secs(15);
+
+/// What an event is about. Clients subscribe to the topics they care about.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Topic {
+    /// Everything anyone can see, such as newly published products.
+    Global,
+    Product(Uuid),
+    /// What only the user can see, such as their notifications.
+    User(i32),
+}
+
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub topic: Topic,
+    pub name: &'static str,
+    pub data: Value,
+}
+
+impl Event {
+    pub fn new(topic: Topic, name: &'static str, data: Value) -> Event {
+        Event { topic, name, data }
+    }
+
+    /// Formats the event for the `text/event-stream` wire format.
+    fn encode(&self) -> Vec<u8> {
+        format!("event: {}\ndata: {}\n\n", self.name, self.data).into_bytes()
+    }
+}
+
+struct Subscriber {
+    topics: Vec<Topic>,
+    sender: SyncSender<Arc<Event>>,
+}
+
+/// Frees the place of a stream among the open ones once it is dropped.
+struct Slot(Arc<AtomicUsize>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Hands events over to the streams subscribed to their topic, within this
+/// process. Every event goes through `publish`, so that events of other
+/// processes can be fed in later, e.g. from Postgres LISTEN/NOTIFY.
+#[derive(Clone)]
+pub struct Broadcaster {
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+    open: Arc<AtomicUsize>,
+    max_streams: usize,
+}
+
+impl Broadcaster {
+    /// Every open stream holds on to a Rocket worker, so `max_streams` has to
+    /// stay below the number of workers for other requests to be served.
+    pub fn new(max_streams: usize) -> Broadcaster {
+        Broadcaster {
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+            open: Arc::new(AtomicUsize::new(0)),
+            max_streams,
+        }
+    }
+
+    /// Opens a stream of the topics, or `None` when `max_streams` are open.
+    pub fn subscribe(&self, topics: Vec<Topic>) -> Option<EventStream> {
+        // Taking the place first and giving it back keeps the count exact
+        // under concurrent subscriptions.
+        if self.open.fetch_add(1, Ordering::SeqCst) >= self.max_streams {
+            self.open.fetch_sub(1, Ordering::SeqCst);
+            return None;
+        }
+        let slot = Slot(self.open.clone());
+        let (sender, receiver) = sync_channel(BACKLOG);
+        self.subscribers
+            .lock()
+            .unwrap()
+            .push(Subscriber { topics, sender });
+        Some(EventStream::new(receiver, slot))
+    }
+
+    /// Sends the event to its subscribers, dropping the ones which are gone or
+    /// too far behind.
+    pub fn publish(&self, event: Event) {
+        let event = Arc::new(event);
+        self.subscribers.lock().unwrap().retain(|s| {
+            if !s.topics.contains(&event.topic) {
+                return true;
+            }
+            match s.sender.try_send(event.clone()) {
+                Ok(_) => true,
+                Err(TrySendError::Full(_)) | Err(TrySendError::Disconnected(_)) => false,
+            }
+        })
+    }
+}
+
+/// Body of an event stream response. Reading blocks until the next event, and
+/// a `WouldBlock` error after each one asks Rocket to flush it to the client.
+pub struct EventStream {
+    receiver: Receiver<Arc<Event>>,
+    buffer: Vec<u8>,
+    position: usize,
+    flush: bool,
+    _slot: Slot,
+}
+
+impl EventStream {
+    fn new(receiver: Receiver<Arc<Event>>, slot: Slot) -> EventStream {
+        EventStream {
+            receiver,
+            buffer: Vec::new(),
+            position: 0,
+            flush: false,
+            _slot: slot,
+        }
+    }
+}
+
+impl Read for EventStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position == self.buffer.len() {
+            if self.flush {
+                self.flush = false;
+                return Err(io::Error::new(io::ErrorKind::WouldBlock, "flush"));
+            }
+            self.buffer = match self.receiver.recv_timeout(KEEPALIVE) {
+                Ok(event) => event.encode(),
+                Err(RecvTimeoutError::Timeout) => b":\n\n".to_vec(),
+                Err(RecvTimeoutError::Disconnected) => return Ok(0),
+            };
+            self.position = 0;
+        }
+        let n = buf.len().min(self.buffer.len() - self.position);
+        buf[..n].copy_from_slice(&self.buffer[self.position..self.position + n]);
+        self.position += n;
+        if self.position == self.buffer.len() {
+            if n == buf.len() {
+                // The chunk is full, so it is written out without reading
+                // again; a blank line, which the client ignores, makes room
+                // for the flush request in the next chunk.
+                self.buffer = b"\n".to_vec();
+                self.position = 0;
+            } else {
+                self.flush = true;
+            }
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Broadcaster, Event, Topic};
+    use std::io::{ErrorKind, Read};
+
+    #[test]
+    fn stream_receives_subscribed_topics_and_asks_for_flush() {
+        let broadcaster = Broadcaster::new(1);
+        let mut stream = broadcaster.subscribe(vec![Topic::User(1)]).unwrap();
+        broadcaster.publish(Event::new(Topic::User(2), "notification", json!(1).into()));
+        broadcaster.publish(Event::new(Topic::User(1), "notification", json!(2).into()));
+
+        let mut buf = [0; 64];
+        let n = stream.read(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"event: notification\ndata: 2\n\n");
+        assert_eq!(
+            stream.read(&mut buf).unwrap_err().kind(),
+            ErrorKind::WouldBlock
+        );
+    }
+
+    #[test]
+    fn dropped_stream_is_unsubscribed() {
+        let broadcaster = Broadcaster::new(1);
+        drop(broadcaster.subscribe(vec![Topic::Global]));
+        broadcaster.publish(Event::new(Topic::Global, "product", json!({}).into()));
+        assert!(broadcaster.subscribers.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn subscribe_refuses_streams_past_the_limit() {
+        let broadcaster = Broadcaster::new(1);
+        let stream = broadcaster.subscribe(vec![Topic::Global]);
+        assert!(stream.is_some());
+        assert!(broadcaster.subscribe(vec![Topic::Global]).is_none());
+        drop(stream);
+        assert!(broadcaster.subscribe(vec![Topic::Global]).is_some());
+    }
+}
@@ -10,15 +10,24 @@ extern crate validator_derive;
 #[macro_use]
 extern crate diesel;
 
+mod background;
 pub mod db;
 mod email;
 mod error;
+mod events;
+pub mod links;
+mod markdown;
 mod models;
+mod net;
 mod routes;
 mod s3;
 mod schema;
+mod slug;
 mod token;
+pub mod trash;
+pub mod trending;
 mod validation;
+pub mod webhooks;
 
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
@@ -39,10 +48,14 @@ pub fn test_establish_connection() -> PgConnection {
 }
 
 pub fn establish_connection() -> PgConnection {
+    try_establish_connection().unwrap_or_else(|e| panic!("Error connecting to database: {}", e))
+}
+
+pub fn try_establish_connection() -> ConnectionResult<PgConnection> {
     dotenv().ok();
 
     let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    PgConnection::establish(&database_url).expect(&format!("Error connecting to {}", database_url))
+    PgConnection::establish(&database_url)
 }
 pub fn rocket() -> rocket::Rocket {
     let allowed_origins =
@@ -69,19 +82,76 @@ pub fn rocket() -> rocket::Rocket {
                 routes::products::post_products,
                 routes::products::update_products,
                 routes::products::delete_products,
+                routes::products::restore,
+                routes::products::get_trash,
+                routes::products::publish,
+                routes::products::archive,
+                routes::products::unarchive,
                 routes::products::get,
+                routes::products::get_by_slug,
+                routes::products::get_derived,
                 routes::products::get_by_user_id,
                 routes::products::recent,
                 routes::products::popular,
+                routes::products::trending,
+                routes::bookmarks::get_all,
+                routes::bookmarks::add,
+                routes::bookmarks::remove,
+                routes::collections::post_collections,
+                routes::collections::update_collections,
+                routes::collections::delete_collections,
+                routes::collections::get,
+                routes::collections::get_by_user_id,
+                routes::collections::add_item,
+                routes::collections::remove_item,
+                routes::collections::reorder_items,
+                routes::comments::get_by_product_id,
+                routes::comments::post_comments,
+                routes::comments::update_comments,
+                routes::comments::delete_comments,
+                routes::devlogs::get_by_product_id,
+                routes::devlogs::post_devlogs,
+                routes::devlogs::update_devlogs,
+                routes::devlogs::delete_devlogs,
+                routes::media::get_by_product_id,
+                routes::media::post_media,
+                routes::media::reorder_media,
+                routes::media::delete_media,
+                routes::events::stream,
+                routes::members::get_by_product_id,
+                routes::members::invite,
+                routes::members::accept,
+                routes::members::remove,
+                routes::notifications::get_notifications,
+                routes::notifications::unread_count,
+                routes::notifications::mark_read,
+                routes::notifications::mark_all_read,
+                routes::revisions::get_by_product_id,
+                routes::revisions::diff,
+                routes::revisions::restore,
                 routes::tags::get_all,
+                routes::views::analytics,
+                routes::webhooks::get_webhooks,
+                routes::webhooks::post_webhooks,
+                routes::webhooks::delete_webhooks,
+                routes::webhooks::get_deliveries,
                 routes::s3::upload,
+                routes::reactions::get_kinds,
                 routes::reactions::add_react,
                 routes::reactions::sub_react,
+                routes::reactions::add_react_by_slug,
+                routes::reactions::sub_react_by_slug,
                 routes::reactions::get_by_user_id,
+                routes::reactions::get_received,
+                routes::reactions::get_given,
                 routes::suggestions::suggestion,
             ],
         )
         .attach(cors)
         .attach(db::Conn::fairing())
         .manage(s3::initial_s3_client())
+        // Every event stream keeps one of the `workers` of Rocket.toml busy.
+        .manage(events::Broadcaster::new(
+            background::env_u64("EVENT_STREAMS_MAX", 48) as usize,
+        ))
 }
@@ -0,0 +1,186 @@
+use crate::background;
+use crate::db;
+use crate::net;
+use diesel::pg::PgConnection;
+use diesel::result::Error;
+use reqwest::header::LOCATION;
+use reqwest::{Client, Method, RedirectPolicy, StatusCode, Url};
+use std::env;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Answers the HTTP status of a link, or why it could not be fetched.
+pub trait Resolver: Send {
+    fn resolve(&self, url: &str) -> Result<u16, String>;
+}
+
+/// How many redirects are followed before giving up on a link.
+const MAX_REDIRECTS: usize = 5;
+
+pub struct HttpResolver {
+    client: Client,
+    /// Whether links have to point at public addresses. Only the stub server,
+    /// whose address we configure ourselves, is allowed to be private.
+    public_only: bool,
+}
+
+impl HttpResolver {
+    pub fn new() -> HttpResolver {
+        HttpResolver::build(true)
+    }
+
+    fn build(public_only: bool) -> HttpResolver {
+        // Redirects are followed by `status`, so that every url they lead to
+        // is checked and requested at the address it was checked at.
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .redirect(RedirectPolicy::none())
+            .build()
+            .unwrap();
+        HttpResolver {
+            client,
+            public_only,
+        }
+    }
+
+    fn status(&self, method: Method, url: &str) -> Result<StatusCode, String> {
+        let mut url = Url::parse(url).map_err(|e| format!("{}", e))?;
+        for _ in 0..=MAX_REDIRECTS {
+            let request = if self.public_only {
+                net::public_request(&self.client, method.clone(), &url)?
+            } else {
+                self.client.request(method.clone(), url.clone())
+            };
+            let response = request.send().map_err(|e| format!("{}", e))?;
+            let location = response
+                .headers()
+                .get(LOCATION)
+                .and_then(|l| l.to_str().ok());
+            match location {
+                Some(location) if response.status().is_redirection() => {
+                    url = url.join(location).map_err(|e| format!("{}", e))?;
+                }
+                _ => return Ok(response.status()),
+            }
+        }
+        Err(format!("{} redirects too many times", url))
+    }
+}
+
+impl Resolver for HttpResolver {
+    fn resolve(&self, url: &str) -> Result<u16, String> {
+        let status = self.status(Method::HEAD, url)?;
+        // Some servers refuse HEAD requests, so ask them again with GET.
+        if status != StatusCode::METHOD_NOT_ALLOWED {
+            return Ok(status.as_u16());
+        }
+        self.status(Method::GET, url).map(|s| s.as_u16())
+    }
+}
+
+/// Sends every link to a fixed base url (keeping its path and query) so that
+/// the checker can run against a local stub server.
+pub struct StubResolver {
+    base: Url,
+    inner: HttpResolver,
+}
+
+impl StubResolver {
+    pub fn new(base: &str) -> Result<StubResolver, String> {
+        let base = Url::parse(base).map_err(|e| format!("{}", e))?;
+        Ok(StubResolver {
+            base,
+            inner: HttpResolver::build(false),
+        })
+    }
+
+    pub fn rewrite(&self, url: &str) -> Result<String, String> {
+        let url = Url::parse(url).map_err(|e| format!("{}", e))?;
+        let mut rewritten = self.base.clone();
+        rewritten.set_path(url.path());
+        rewritten.set_query(url.query());
+        Ok(rewritten.to_string())
+    }
+}
+
+impl Resolver for StubResolver {
+    fn resolve(&self, url: &str) -> Result<u16, String> {
+        self.inner.resolve(&self.rewrite(url)?)
+    }
+}
+
+/// Uses `LINK_CHECK_STUB_URL` as a stub server when it is set.
+pub fn resolver_from_env() -> Box<dyn Resolver> {
+    match env::var("LINK_CHECK_STUB_URL") {
+        Ok(base) => Box::new(StubResolver::new(&base).expect("invalid LINK_CHECK_STUB_URL")),
+        Err(_) => Box::new(HttpResolver::new()),
+    }
+}
+
+/// Checks up to `limit` links which have not been checked for `max_age`.
+pub fn check_stale(
+    conn: &PgConnection,
+    resolver: &dyn Resolver,
+    max_age: Duration,
+    limit: i64,
+) -> Result<usize, Error> {
+    let links = db::links::find_stale(conn, SystemTime::now() - max_age, limit)?;
+    for link in links.iter() {
+        match resolver.resolve(&link.url) {
+            Ok(status) => db::links::record_check(conn, &link.id, Some(status as i32), None)?,
+            Err(e) => db::links::record_check(conn, &link.id, None, Some(e))?,
+        };
+    }
+    Ok(links.len())
+}
+
+/// Keeps checking links in the background, every `LINK_CHECK_INTERVAL` seconds
+/// (an hour by default).
+pub fn spawn_checker() -> thread::JoinHandle<()> {
+    let interval = background::interval("LINK_CHECK_INTERVAL", 60 * 60);
+    let resolver = resolver_from_env();
+    thread::spawn(move || loop {
+        let conn = background::connect("Link checker");
+        loop {
+            match check_stale(&conn, &*resolver, interval, 100) {
+                Ok(checked) if checked > 0 => continue,
+                Ok(_) => break,
+                Err(e) => {
+                    println!("Could not check links: {:?}", e);
+                    break;
+                }
+            }
+        }
+        thread::sleep(interval);
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{HttpResolver, Resolver, StubResolver};
+    use crate::net::stub_server;
+
+    #[test]
+    fn stub_resolver_keeps_path_and_query() {
+        let resolver = StubResolver::new("http://127.0.0.1:8080").unwrap();
+        assert_eq!(
+            resolver.rewrite("https://github.com/anharu2394/tentech-api?tab=readme"),
+            Ok("http://127.0.0.1:8080/anharu2394/tentech-api?tab=readme".to_string())
+        );
+    }
+
+    #[test]
+    fn stub_resolver_reports_status() {
+        let resolver = StubResolver::new(&stub_server("404 Not Found", 2).0).unwrap();
+        assert_eq!(resolver.resolve("https://example.com/missing"), Ok(404));
+    }
+
+    #[test]
+    fn http_resolver_refuses_private_addresses() {
+        let resolver = HttpResolver::new();
+        assert!(resolver.resolve(&stub_server("200 OK", 2).0).is_err());
+        assert!(resolver
+            .resolve("http://169.254.169.254/latest/meta-data")
+            .is_err());
+    }
+}
@@ -1,9 +1,14 @@
 use tentech_api;
 
 fn main() {
-    match tentech_api::db::tags::init(&tentech_api::establish_connection()) {
+    let conn = tentech_api::establish_connection();
+    match tentech_api::db::tags::init(&conn) {
         Err(e) => println!("{:?}", e),
         _ => {}
     }
+    tentech_api::links::spawn_checker();
+    tentech_api::trash::spawn_purger();
+    tentech_api::trending::spawn_refresher();
+    tentech_api::webhooks::spawn_dispatcher();
     tentech_api::rocket().launch();
 }
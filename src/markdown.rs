@@ -0,0 +1,149 @@
+use ammonia::Builder;
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag};
+use std::collections::{HashMap, HashSet};
+
+const TAGS: &[&str] = &[
+    "a",
+    "blockquote",
+    "br",
+    "code",
+    "del",
+    "em",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "img",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "strong",
+    "table",
+    "tbody",
+    "td",
+    "th",
+    "thead",
+    "tr",
+    "ul",
+];
+
+/// Turns a heading into the id of its anchor, keeping letters of any script.
+fn anchor(text: &str) -> String {
+    let mut anchor = String::new();
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            anchor.extend(c.to_lowercase());
+        } else if !anchor.is_empty() && !anchor.ends_with('-') {
+            anchor.push('-');
+        }
+    }
+    let anchor = anchor.trim_end_matches('-');
+    if anchor.is_empty() {
+        "section".to_string()
+    } else {
+        anchor.to_string()
+    }
+}
+
+/// Gives every heading an id so that sections can be linked to.
+fn with_anchors<'a>(parser: Parser<'a>) -> Vec<Event<'a>> {
+    let mut events = Vec::new();
+    let mut heading: Option<Vec<Event<'a>>> = None;
+    let mut used: HashMap<String, usize> = HashMap::new();
+    for event in parser {
+        match event {
+            Event::Start(Tag::Heading(_)) => heading = Some(Vec::new()),
+            Event::End(Tag::Heading(level)) => {
+                let inner = heading.take().unwrap_or_default();
+                let text: String = inner
+                    .iter()
+                    .filter_map(|e| match e {
+                        Event::Text(t) | Event::Code(t) => Some(t.to_string()),
+                        _ => None,
+                    })
+                    .collect();
+                let base = anchor(&text);
+                let count = used.entry(base.clone()).or_insert(0);
+                let id = if *count == 0 {
+                    base
+                } else {
+                    format!("{}-{}", base, count)
+                };
+                *count += 1;
+                events.push(Event::Html(CowStr::from(format!(
+                    "<h{} id=\"{}\">",
+                    level, id
+                ))));
+                events.extend(inner);
+                events.push(Event::Html(CowStr::from(format!("</h{}>\n", level))));
+            }
+            event => match heading {
+                Some(ref mut inner) => inner.push(event),
+                None => events.push(event),
+            },
+        }
+    }
+    events
+}
+
+/// Renders markdown to HTML which is safe to embed in a page: only an
+/// allowlist of tags survives, and links are marked `rel="nofollow"`.
+pub fn render(source: &str) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    let mut unsafe_html = String::new();
+    html::push_html(
+        &mut unsafe_html,
+        with_anchors(Parser::new_ext(source, options)).into_iter(),
+    );
+
+    let mut tag_attributes = HashMap::new();
+    tag_attributes.insert("a", ["href", "title"].iter().cloned().collect());
+    tag_attributes.insert("img", ["src", "alt", "title"].iter().cloned().collect());
+    for h in &["h1", "h2", "h3", "h4", "h5", "h6"] {
+        tag_attributes.insert(*h, ["id"].iter().cloned().collect());
+    }
+    Builder::default()
+        .tags(TAGS.iter().cloned().collect())
+        .tag_attributes(tag_attributes)
+        .generic_attributes(HashSet::new())
+        .url_schemes(["http", "https", "mailto"].iter().cloned().collect())
+        .link_rel(Some("nofollow"))
+        .clean(&unsafe_html)
+        .to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::render;
+
+    #[test]
+    fn render_strips_scripts_and_handlers() {
+        let html =
+            render("hello<script>alert(1)</script> <img src=\"x.png\" onerror=\"alert(1)\">");
+        assert!(!html.contains("script"));
+        assert!(!html.contains("onerror"));
+        assert!(html.contains("<img src=\"x.png\">"));
+    }
+
+    #[test]
+    fn render_marks_links_nofollow() {
+        assert_eq!(
+            render("[tentech](https://tentech.dev) [x](javascript:alert(1))"),
+            "<p><a href=\"https://tentech.dev\" rel=\"nofollow\">tentech</a> <a rel=\"nofollow\">x</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn render_anchors_headings() {
+        assert_eq!(
+            render("# Getting Started\n## 使い方\n# Getting started"),
+            "<h1 id=\"getting-started\">Getting Started</h1>\n<h2 id=\"使い方\">使い方</h2>\n<h1 id=\"getting-started-1\">Getting started</h1>\n"
+        );
+    }
+}
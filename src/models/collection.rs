@@ -0,0 +1,26 @@
+use crate::models::user::User;
+use crate::schema::collections;
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Queryable, Serialize, Deserialize, Identifiable, Associations)]
+#[belongs_to(parent = "User")]
+#[table_name = "collections"]
+pub struct Collection {
+    pub id: i32,
+    pub uuid: Uuid,
+    pub user_id: i32,
+    pub name: String,
+    pub description: String,
+    pub public: bool,
+    pub created_at: SystemTime,
+    pub updated_at: SystemTime,
+}
+
+impl Collection {
+    /// Private collections are only visible to their owner.
+    pub fn is_visible_to(&self, user_id: Option<i32>) -> bool {
+        self.public || Some(self.user_id) == user_id
+    }
+}
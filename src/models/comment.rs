@@ -0,0 +1,35 @@
+use crate::models::product::Product;
+use crate::models::user::User;
+use crate::schema::comments;
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, Queryable, Serialize, Deserialize, Identifiable, Associations)]
+#[belongs_to(parent = "Product")]
+#[belongs_to(parent = "User")]
+#[table_name = "comments"]
+pub struct Comment {
+    pub id: i32,
+    pub product_id: i32,
+    pub user_id: i32,
+    pub parent_id: Option<i32>,
+    pub body: String,
+    pub deleted_at: Option<SystemTime>,
+    pub created_at: SystemTime,
+    pub updated_at: SystemTime,
+    pub body_html: String,
+}
+
+impl Comment {
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+    /// Deleted comments stay in threads as tombstones without their body.
+    pub fn hide_deleted(mut self) -> Comment {
+        if self.is_deleted() {
+            self.body = String::new();
+            self.body_html = String::new();
+        }
+        self
+    }
+}
@@ -0,0 +1,21 @@
+use crate::models::product::Product;
+use crate::schema::devlogs;
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Queryable, Serialize, Deserialize, Identifiable, Associations)]
+#[belongs_to(parent = "Product")]
+#[table_name = "devlogs"]
+pub struct Devlog {
+    pub id: i32,
+    pub uuid: Uuid,
+    pub product_id: i32,
+    pub user_id: i32,
+    pub body: String,
+    pub hours: Option<i32>,
+    pub img: Option<String>,
+    pub created_at: SystemTime,
+    pub updated_at: SystemTime,
+    pub body_html: String,
+}
@@ -0,0 +1,22 @@
+use crate::models::product::Product;
+use crate::schema::product_links;
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+
+pub const REPO: &str = "repo";
+pub const DEMO: &str = "demo";
+pub const DOCS: &str = "docs";
+pub const OTHER: &str = "other";
+
+#[derive(Debug, Clone, Queryable, Serialize, Deserialize, Identifiable, Associations)]
+#[belongs_to(parent = "Product")]
+#[table_name = "product_links"]
+pub struct Link {
+    pub id: i32,
+    pub product_id: i32,
+    pub kind: String,
+    pub url: String,
+    pub status: Option<i32>,
+    pub error: Option<String>,
+    pub checked_at: Option<SystemTime>,
+}
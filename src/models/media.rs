@@ -0,0 +1,27 @@
+use crate::models::product::Product;
+use crate::schema::product_media;
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+use uuid::Uuid;
+
+pub const IMAGE: &str = "image";
+pub const VIDEO: &str = "video";
+
+#[derive(Debug, Clone, Queryable, Serialize, Deserialize, Identifiable, Associations)]
+#[belongs_to(parent = "Product")]
+#[table_name = "product_media"]
+pub struct Media {
+    pub id: i32,
+    pub uuid: Uuid,
+    pub product_id: i32,
+    pub kind: String,
+    pub storage_key: String,
+    pub caption: String,
+    pub alt: String,
+    pub width: i32,
+    pub height: i32,
+    pub position: i32,
+    pub created_at: SystemTime,
+    /// Who uploaded the stored object, unknown for media older than tracking uploads.
+    pub user_id: Option<i32>,
+}
@@ -0,0 +1,33 @@
+use crate::models::product::Product;
+use crate::schema::product_members;
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+
+pub const OWNER: &str = "owner";
+pub const EDITOR: &str = "editor";
+
+#[derive(Debug, Clone, Queryable, Serialize, Deserialize, Identifiable, Associations)]
+#[belongs_to(parent = "Product")]
+#[table_name = "product_members"]
+pub struct Member {
+    pub id: i32,
+    pub product_id: i32,
+    pub user_id: i32,
+    pub role: String,
+    pub invited_by: Option<i32>,
+    pub accepted_at: Option<SystemTime>,
+    pub created_at: SystemTime,
+}
+
+impl Member {
+    pub fn is_accepted(&self) -> bool {
+        self.accepted_at.is_some()
+    }
+    pub fn is_owner(&self) -> bool {
+        self.is_accepted() && self.role == OWNER
+    }
+    /// Owners and editors who accepted their invitation can edit the product.
+    pub fn can_edit(&self) -> bool {
+        self.is_accepted() && (self.role == OWNER || self.role == EDITOR)
+    }
+}
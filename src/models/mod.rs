@@ -1,5 +1,15 @@
+pub mod collection;
+pub mod comment;
+pub mod devlog;
+pub mod link;
+pub mod media;
+pub mod member;
+pub mod notification;
 pub mod product;
 pub mod reaction;
+pub mod revision;
 pub mod suggestion;
 pub mod tag;
 pub mod user;
+pub mod view;
+pub mod webhook;
@@ -0,0 +1,76 @@
+use crate::models::user::User;
+use crate::schema::notifications;
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+use uuid::Uuid;
+
+pub const REACTION: &str = "reaction";
+pub const COMMENT: &str = "comment";
+pub const INVITE: &str = "invite";
+
+#[derive(Debug, Clone, Queryable, Serialize, Deserialize, Identifiable, Associations)]
+#[belongs_to(parent = "User")]
+#[table_name = "notifications"]
+pub struct Notification {
+    pub id: i32,
+    pub uuid: Uuid,
+    pub user_id: i32,
+    pub kind: String,
+    pub product_id: Option<i32>,
+    /// What the event was about within the product, such as the reaction kind.
+    pub subject: String,
+    /// Everyone behind the folded events, the latest first.
+    pub actor_ids: Vec<i32>,
+    pub created_at: SystemTime,
+    pub updated_at: SystemTime,
+    pub read_at: Option<SystemTime>,
+}
+
+impl Notification {
+    pub fn is_read(&self) -> bool {
+        self.read_at.is_some()
+    }
+}
+
+/// Describes a notification, naming the actors while there are no more than
+/// two, whatever the number of `names` given for the latest of them. `total`
+/// is how many actors there are in all, some of whom may have been deleted.
+pub fn message(kind: &str, names: &[&str], total: usize, subject: &str, title: &str) -> String {
+    let who = match total {
+        1 if !names.is_empty() => names[0].to_string(),
+        2 if names.len() >= 2 => format!("{} and {}", names[0], names[1]),
+        1 => "Someone".to_string(),
+        _ => format!("{} people", total),
+    };
+    match kind {
+        REACTION => format!("{} reacted {} to {}", who, subject, title),
+        COMMENT => format!("{} commented on {}", who, title),
+        INVITE => format!("{} invited you to {}", who, title),
+        _ => who,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{message, COMMENT, REACTION};
+
+    #[test]
+    fn message_names_few_and_counts_many() {
+        assert_eq!(
+            message(REACTION, &["anharu"], 1, "🔥", "Tentech"),
+            "anharu reacted 🔥 to Tentech"
+        );
+        assert_eq!(
+            message(COMMENT, &["anharu", "bob"], 2, "", "Tentech"),
+            "anharu and bob commented on Tentech"
+        );
+        assert_eq!(
+            message(REACTION, &["anharu", "bob"], 3, "🔥", "Tentech"),
+            "3 people reacted 🔥 to Tentech"
+        );
+        assert_eq!(
+            message(COMMENT, &[], 1, "", "Tentech"),
+            "Someone commented on Tentech"
+        );
+    }
+}
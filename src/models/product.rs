@@ -2,8 +2,13 @@ use crate::models::user::User;
 use crate::schema::products;
 use diesel::associations;
 use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
 use uuid::Uuid;
 
+pub const DRAFT: &str = "draft";
+pub const PUBLISHED: &str = "published";
+pub const ARCHIVED: &str = "archived";
+
 #[derive(
     Debug, Clone, Queryable, Serialize, Deserialize, Identifiable, Associations, QueryableByName,
 )]
@@ -20,4 +25,22 @@ pub struct Product {
     pub duration: i32,
     pub user_id: i32,
     pub simple: String,
+    pub state: String,
+    pub published_at: Option<SystemTime>,
+    pub created_at: SystemTime,
+    pub updated_at: SystemTime,
+    pub slug: String,
+    pub duration_from_devlogs: bool,
+    pub body_html: String,
+    pub deleted_at: Option<SystemTime>,
+    pub inspired_by_id: Option<i32>,
+}
+
+impl Product {
+    pub fn is_published(&self) -> bool {
+        self.state == PUBLISHED
+    }
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
 }
@@ -1,7 +1,7 @@
 use crate::db;
 use crate::models::product::Product;
 use crate::models::user::User;
-use crate::schema::reactions;
+use crate::schema::{reaction_kinds, reactions};
 use diesel::associations;
 use diesel::pg::PgConnection;
 use diesel::Identifiable;
@@ -20,3 +20,15 @@ pub struct Reaction {
     pub kind: String,
     pub created_at: SystemTime,
 }
+
+#[derive(Identifiable, Clone, Queryable, Serialize, Deserialize)]
+#[primary_key(name)]
+#[table_name = "reaction_kinds"]
+pub struct ReactionKind {
+    pub name: String,
+    pub emoji: String,
+    pub label: String,
+    pub position: i32,
+    pub max_per_user: i32,
+    pub weight: i32,
+}
@@ -0,0 +1,111 @@
+use crate::models::product::Product;
+use crate::schema::product_revisions;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, Queryable, Serialize, Deserialize, Identifiable, Associations)]
+#[belongs_to(parent = "Product")]
+#[table_name = "product_revisions"]
+pub struct Revision {
+    pub id: i32,
+    pub product_id: i32,
+    pub number: i32,
+    pub user_id: i32,
+    pub title: String,
+    pub body: String,
+    pub simple: String,
+    pub img: String,
+    pub kind: String,
+    pub status: String,
+    pub duration: i32,
+    pub tag_ids: Vec<i32>,
+    pub created_at: SystemTime,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub from: Value,
+    pub to: Value,
+}
+
+fn push_change<T: PartialEq + Serialize>(
+    changes: &mut Vec<FieldChange>,
+    field: &'static str,
+    from: &T,
+    to: &T,
+) {
+    if from != to {
+        changes.push(FieldChange {
+            field,
+            from: serde_json::to_value(from).unwrap_or(Value::Null),
+            to: serde_json::to_value(to).unwrap_or(Value::Null),
+        });
+    }
+}
+
+impl Revision {
+    /// Lists the fields which differ between this revision and `other`.
+    pub fn diff(&self, other: &Revision) -> Vec<FieldChange> {
+        let mut changes = Vec::new();
+        push_change(&mut changes, "title", &self.title, &other.title);
+        push_change(&mut changes, "body", &self.body, &other.body);
+        push_change(&mut changes, "simple", &self.simple, &other.simple);
+        push_change(&mut changes, "img", &self.img, &other.img);
+        push_change(&mut changes, "kind", &self.kind, &other.kind);
+        push_change(&mut changes, "status", &self.status, &other.status);
+        push_change(&mut changes, "duration", &self.duration, &other.duration);
+
+        let mut from_tags = self.tag_ids.clone();
+        let mut to_tags = other.tag_ids.clone();
+        from_tags.sort();
+        to_tags.sort();
+        push_change(&mut changes, "tag_ids", &from_tags, &to_tags);
+        changes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Revision;
+    use std::time::SystemTime;
+
+    fn revision(number: i32, title: &str, tag_ids: Vec<i32>) -> Revision {
+        Revision {
+            id: number,
+            product_id: 1,
+            number,
+            user_id: 1,
+            title: title.to_string(),
+            body: "body".to_string(),
+            simple: "simple".to_string(),
+            img: "https://example.com/a.png".to_string(),
+            kind: "WebApp".to_string(),
+            status: "developing".to_string(),
+            duration: 3,
+            tag_ids,
+            created_at: SystemTime::now(),
+        }
+    }
+
+    #[test]
+    fn diff_lists_changed_fields_only() {
+        let from = revision(1, "Todo", vec![1, 2]);
+        let to = revision(2, "Todo app", vec![2, 1]);
+        let changes = from.diff(&to);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].field, "title");
+        assert_eq!(changes[0].from, "Todo");
+        assert_eq!(changes[0].to, "Todo app");
+    }
+
+    #[test]
+    fn diff_detects_tag_changes() {
+        let from = revision(1, "Todo", vec![1, 2]);
+        let to = revision(2, "Todo", vec![1, 3]);
+        let changes = from.diff(&to);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].field, "tag_ids");
+    }
+}
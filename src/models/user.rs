@@ -26,6 +26,16 @@ pub struct User {
     pub bio: Option<String>,
 }
 
+/// What anyone can see of a user, leaving out the email and credentials.
+#[derive(Clone, Serialize)]
+pub struct PublicUser {
+    pub id: i32,
+    pub username: String,
+    pub nickname: String,
+    pub avatar: Option<String>,
+    pub bio: Option<String>,
+}
+
 #[derive(Clone, Queryable, Serialize, Deserialize)]
 pub struct TokenData {
     #[serde(flatten)]
@@ -47,6 +57,15 @@ impl User {
         let json = serde_json::to_string(&self.to_token_data()).unwrap();
         encrypt(&json)
     }
+    pub fn to_public(&self) -> PublicUser {
+        PublicUser {
+            id: self.id,
+            username: self.username.clone(),
+            nickname: self.nickname.clone(),
+            avatar: self.avatar.clone(),
+            bio: self.bio.clone(),
+        }
+    }
     pub fn to_token_data(&self) -> TokenData {
         let expired_at = Local::now() + Duration::days(1);
         TokenData {
@@ -0,0 +1,89 @@
+use chrono::Utc;
+use crypto::hmac::Hmac;
+use crypto::mac::Mac;
+use crypto::sha2::Sha256;
+use dotenv::dotenv;
+use reqwest::Url;
+use rocket::request::{self, FromRequest, Request};
+use rocket::Outcome;
+use std::env;
+
+/// Who is looking at a page and where they came from.
+pub struct Visit {
+    /// HMAC of the address and user agent of the client, so that anonymous
+    /// views can be told apart without storing either of them.
+    pub fingerprint: String,
+    /// Host of the `Referer` header, if any.
+    pub referrer: Option<String>,
+}
+
+impl Visit {
+    pub fn viewer(&self, user_id: Option<i32>) -> String {
+        match user_id {
+            Some(id) => format!("user:{}", id),
+            None => format!("anon:{}", self.fingerprint),
+        }
+    }
+}
+
+fn hmac(key: &[u8], message: &str) -> Vec<u8> {
+    let mut hmac = Hmac::new(Sha256::new(), key);
+    hmac.input(message.as_bytes());
+    hmac.result().code().to_vec()
+}
+
+/// Keys the fingerprint with `secret` and the day, so that it can neither be
+/// reversed by hashing every address nor followed from one day to the next.
+pub fn fingerprint(secret: &str, day: &str, ip: &str, user_agent: &str) -> String {
+    let key = hmac(secret.as_bytes(), day);
+    hmac(&key, &format!("{}|{}", ip, user_agent))
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for Visit {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        let ip = request
+            .client_ip()
+            .map(|ip| ip.to_string())
+            .unwrap_or_default();
+        let user_agent = request.headers().get_one("User-Agent").unwrap_or("");
+        dotenv().ok();
+        let secret = env::var("VIEW_SECRET_KEY").expect("VIEW_SECRET_KEY must be set");
+        let day = Utc::today().format("%Y-%m-%d").to_string();
+        let referrer = request
+            .headers()
+            .get_one("Referer")
+            .and_then(|r| Url::parse(r).ok())
+            .and_then(|u| u.host_str().map(|h| h.to_lowercase()));
+        Outcome::Success(Visit {
+            fingerprint: fingerprint(&secret, &day, &ip, user_agent),
+            referrer,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::fingerprint;
+
+    #[test]
+    fn fingerprint_changes_with_secret_and_day() {
+        let today = fingerprint("secret", "2019-10-19", "203.0.113.1", "curl");
+        assert_eq!(
+            today,
+            fingerprint("secret", "2019-10-19", "203.0.113.1", "curl")
+        );
+        assert_ne!(
+            today,
+            fingerprint("secret", "2019-10-20", "203.0.113.1", "curl")
+        );
+        assert_ne!(
+            today,
+            fingerprint("other", "2019-10-19", "203.0.113.1", "curl")
+        );
+    }
+}
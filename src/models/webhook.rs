@@ -0,0 +1,58 @@
+use crate::models::user::User;
+use crate::schema::{webhook_deliveries, webhooks};
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+use uuid::Uuid;
+
+pub const PRODUCT_CREATED: &str = "product.created";
+pub const PRODUCT_UPDATED: &str = "product.updated";
+pub const REACTION_ADDED: &str = "reaction.added";
+pub const USER_ACTIVATED: &str = "user.activated";
+
+pub const EVENTS: [&str; 4] = [
+    PRODUCT_CREATED,
+    PRODUCT_UPDATED,
+    REACTION_ADDED,
+    USER_ACTIVATED,
+];
+
+#[derive(Debug, Clone, Queryable, Serialize, Deserialize, Identifiable, Associations)]
+#[belongs_to(parent = "User")]
+#[table_name = "webhooks"]
+pub struct Webhook {
+    pub id: i32,
+    pub uuid: Uuid,
+    pub user_id: i32,
+    pub url: String,
+    /// Key of the signature of every payload, shown once when the webhook is created.
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub events: Vec<String>,
+    pub tag_ids: Vec<i32>,
+    pub created_at: SystemTime,
+}
+
+impl Webhook {
+    /// Whether the webhook wants the event about a product with `tag_ids`.
+    pub fn wants(&self, event: &str, tag_ids: &[i32]) -> bool {
+        self.events.iter().any(|e| e == event)
+            && (self.tag_ids.is_empty() || self.tag_ids.iter().any(|t| tag_ids.contains(t)))
+    }
+}
+
+#[derive(Debug, Clone, Queryable, Serialize, Deserialize, Identifiable, Associations)]
+#[belongs_to(parent = "Webhook")]
+#[table_name = "webhook_deliveries"]
+pub struct Delivery {
+    pub id: i32,
+    pub uuid: Uuid,
+    pub webhook_id: i32,
+    pub event: String,
+    pub payload: String,
+    pub attempts: i32,
+    pub status: Option<i32>,
+    pub error: Option<String>,
+    pub next_attempt_at: Option<SystemTime>,
+    pub delivered_at: Option<SystemTime>,
+    pub created_at: SystemTime,
+}
@@ -0,0 +1,223 @@
+use reqwest::header::HOST;
+use reqwest::{Client, Method, RequestBuilder, Url};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs};
+
+fn is_public_v4(ip: &Ipv4Addr) -> bool {
+    let octets = ip.octets();
+    !(ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        // Shared address space of carrier-grade NAT.
+        || (octets[0] == 100 && (64..128).contains(&octets[1]))
+        || octets[0] == 0)
+}
+
+fn is_public_v6(ip: &Ipv6Addr) -> bool {
+    if let Some(v4) = ip.to_ipv4() {
+        // IPv4-mapped and -compatible addresses reach the IPv4 host.
+        if ip.segments()[..5].iter().all(|s| *s == 0) {
+            return is_public_v4(&v4);
+        }
+    }
+    let first = ip.segments()[0];
+    !(ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        // Unique local fc00::/7 and link-local fe80::/10.
+        || (first & 0xfe00) == 0xfc00
+        || (first & 0xffc0) == 0xfe80)
+}
+
+/// Whether the address can be reached from the internet, as opposed to
+/// loopback, private, link-local (cloud metadata) or unspecified ones.
+pub fn is_public(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => is_public_v6(ip),
+    }
+}
+
+/// Resolves the host of the http(s) url and refuses it unless every address
+/// it resolves to is public, so that user supplied urls cannot be used to
+/// reach our own network. Answers the first of the addresses.
+pub fn resolve_public(url: &Url) -> Result<SocketAddr, String> {
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(format!("{} is not an http url", url));
+    }
+    let host = url
+        .host_str()
+        .ok_or_else(|| format!("{} has no host", url))?
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let port = url.port_or_known_default().unwrap_or(80);
+    let addrs: Vec<_> = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("{}: {}", host, e))?
+        .collect();
+    if addrs.is_empty() || addrs.iter().any(|a| !is_public(&a.ip())) {
+        return Err(format!("{} is not a public address", host));
+    }
+    Ok(addrs[0])
+}
+
+/// Like `resolve_public`, for urls which are only checked.
+pub fn check_public_url(url: &Url) -> Result<(), String> {
+    resolve_public(url).map(|_| ())
+}
+
+/// Points the url at `addr`, answering it with the `Host` header which names
+/// the original host.
+fn pin(url: &Url, addr: SocketAddr) -> (Url, String) {
+    let mut host = url.host_str().unwrap_or_default().to_string();
+    if let Some(port) = url.port() {
+        host = format!("{}:{}", host, port);
+    }
+    let mut pinned = url.clone();
+    let _ = pinned.set_ip_host(addr.ip());
+    (pinned, host)
+}
+
+/// Builds a request to the address which the host of the url was checked to
+/// resolve to. Letting the client resolve it again when connecting would
+/// reach whatever it points at by then, which may be our own network.
+///
+/// Https urls are requested as they are, since the certificate has to match
+/// their host name, which no server of our own network can present.
+pub fn public_request(
+    client: &Client,
+    method: Method,
+    url: &Url,
+) -> Result<RequestBuilder, String> {
+    let addr = resolve_public(url)?;
+    if url.scheme() == "https" {
+        return Ok(client.request(method, url.clone()));
+    }
+    let (pinned, host) = pin(url, addr);
+    Ok(client.request(method, pinned).header(HOST, host))
+}
+
+/// Serves `count` requests locally, answering each with `status` and handing
+/// over what it received, for tests of outgoing requests.
+#[cfg(test)]
+pub fn stub_server(
+    status: &'static str,
+    count: usize,
+) -> (String, std::sync::mpsc::Receiver<String>) {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (sender, received) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming().take(count) {
+            let mut stream = stream.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            // Reads the headers and as much body as they announce, or up to
+            // the end of the stream.
+            loop {
+                let n = stream.read(&mut buf).unwrap_or(0);
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some(end) = text.find("\r\n\r\n") {
+                    let length = text[..end]
+                        .lines()
+                        .filter_map(|l| {
+                            let mut header = l.splitn(2, ':');
+                            match (header.next(), header.next()) {
+                                (Some(name), Some(value))
+                                    if name.eq_ignore_ascii_case("content-length") =>
+                                {
+                                    value.trim().parse::<usize>().ok()
+                                }
+                                _ => None,
+                            }
+                        })
+                        .next()
+                        .unwrap_or(0);
+                    if request.len() >= end + 4 + length {
+                        break;
+                    }
+                }
+            }
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                status
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+            let _ = sender.send(String::from_utf8_lossy(&request).to_string());
+        }
+    });
+    (format!("http://{}", addr), received)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_public_url, is_public, pin};
+    use reqwest::Url;
+
+    #[test]
+    fn is_public_refuses_internal_addresses() {
+        for ip in &[
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "0.0.0.0",
+            "100.64.0.1",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public(&ip.parse().unwrap()), "{}", ip);
+        }
+        assert!(is_public(&"93.184.216.34".parse().unwrap()));
+        assert!(is_public(&"2606:2800:220:1::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn check_public_url_refuses_internal_hosts() {
+        for url in &[
+            "http://169.254.169.254/latest/meta-data",
+            "http://localhost:5432",
+            "http://[::1]/",
+            "ftp://93.184.216.34/",
+        ] {
+            assert!(
+                check_public_url(&Url::parse(url).unwrap()).is_err(),
+                "{}",
+                url
+            );
+        }
+        assert!(check_public_url(&Url::parse("http://93.184.216.34/").unwrap()).is_ok());
+    }
+
+    #[test]
+    fn pin_keeps_host_header() {
+        let (url, host) = pin(
+            &Url::parse("http://example.com:8080/a?b=c").unwrap(),
+            "93.184.216.34:8080".parse().unwrap(),
+        );
+        assert_eq!(url.as_str(), "http://93.184.216.34:8080/a?b=c");
+        assert_eq!(host, "example.com:8080");
+        let (url, host) = pin(
+            &Url::parse("http://example.com/").unwrap(),
+            "[2606:2800:220:1::1]:80".parse().unwrap(),
+        );
+        assert_eq!(url.as_str(), "http://[2606:2800:220:1::1]/");
+        assert_eq!(host, "example.com");
+    }
+}
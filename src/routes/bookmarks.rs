@@ -0,0 +1,30 @@
+use crate::db;
+use crate::error::TentechError;
+use crate::models::user::TokenData;
+use crate::routes::products::{check_visible, find_product, listing};
+use rocket_contrib::json::JsonValue;
+
+#[get("/bookmarks")]
+pub fn get_all(conn: db::Conn, token: TokenData) -> Result<JsonValue, TentechError> {
+    db::bookmarks::find_products_by_user_id(&conn, &token.user.id)
+        .and_then(|ps| listing(&conn, &ps, Some(token.user.id)))
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|ps| json!({ "products": ps }))
+}
+
+#[post("/products/<id>/bookmark")]
+pub fn add(conn: db::Conn, token: TokenData, id: String) -> Result<JsonValue, TentechError> {
+    let product = find_product(&conn, &id)?;
+    check_visible(&conn, &product, Some(token.user.id))?;
+    db::bookmarks::add(&conn, &token.user.id, &product.id)
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|_| json!({}))
+}
+
+#[delete("/products/<id>/bookmark")]
+pub fn remove(conn: db::Conn, token: TokenData, id: String) -> Result<JsonValue, TentechError> {
+    let product = find_product(&conn, &id)?;
+    db::bookmarks::remove(&conn, &token.user.id, &product.id)
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|_| json!({}))
+}
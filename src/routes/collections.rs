@@ -0,0 +1,247 @@
+use crate::db;
+use crate::db::collections::NewCollection as NewCollectionRecord;
+use crate::error::TentechError;
+use crate::models::collection::Collection;
+use crate::models::user::TokenData;
+use crate::routes::products::{check_visible, find_product, listing};
+use crate::validation::{field_error, FieldValidator};
+use diesel::pg::PgConnection;
+use diesel::result::Error;
+use rocket_contrib::json::{Json, JsonValue};
+use serde::Deserialize;
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Deserialize)]
+pub struct NewCollection {
+    collection: NewCollectionData,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct NewCollectionData {
+    #[validate(length(min = "1", max = "50"))]
+    name: Option<String>,
+    #[validate(length(max = "500"))]
+    description: Option<String>,
+    public: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct NewItem {
+    item: NewItemData,
+}
+
+#[derive(Deserialize)]
+pub struct NewItemData {
+    product_id: String,
+}
+
+#[derive(Deserialize)]
+pub struct ItemOrder {
+    product_ids: Vec<String>,
+}
+
+/// Looks up the collection addressed by `id`, answering 404 for malformed or unknown ids.
+fn find_collection(conn: &PgConnection, id: &str) -> Result<Collection, TentechError> {
+    let not_found = || TentechError::NotFound("Collection not found".to_string());
+    let uuid = Uuid::parse_str(id).map_err(|_| not_found())?;
+    db::collections::find(conn, &uuid).map_err(|e| match e {
+        Error::NotFound => not_found(),
+        e => TentechError::DatabaseFailed(format!("{}", e)),
+    })
+}
+
+fn find_own_collection(
+    conn: &PgConnection,
+    token: &TokenData,
+    id: &str,
+) -> Result<Collection, TentechError> {
+    let collection = find_collection(conn, id)?;
+    if !(collection.user_id == token.user.id) {
+        return Err(TentechError::Unauthorized(
+            "Cannot change other's collection".to_string(),
+        ));
+    }
+    Ok(collection)
+}
+
+#[post("/collections", format = "json", data = "<new_collection>")]
+pub fn post_collections(
+    new_collection: Json<NewCollection>,
+    conn: db::Conn,
+    token: TokenData,
+) -> Result<JsonValue, TentechError> {
+    let new_collection = new_collection.into_inner().collection;
+
+    let mut extractor = FieldValidator::validate(&new_collection);
+    let name = extractor.extract("name", new_collection.name);
+
+    extractor
+        .check()
+        .map_err(|e| TentechError::ValidationFailed(e.errors))?;
+
+    let record = &NewCollectionRecord {
+        name: &name,
+        description: &new_collection.description.unwrap_or_default(),
+        public: &new_collection.public.unwrap_or(false),
+    };
+    db::collections::create(&conn, &token.user.id, record)
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|c| json!({ "collection": c }))
+}
+
+#[patch("/collections/<id>", format = "json", data = "<update_collection>")]
+pub fn update_collections(
+    update_collection: Json<NewCollection>,
+    conn: db::Conn,
+    token: TokenData,
+    id: String,
+) -> Result<JsonValue, TentechError> {
+    let collection = find_own_collection(&conn, &token, &id)?;
+    let update_collection = update_collection.into_inner().collection;
+
+    let mut extractor = FieldValidator::validate(&update_collection);
+    let name = extractor.extract("name", update_collection.name);
+
+    extractor
+        .check()
+        .map_err(|e| TentechError::ValidationFailed(e.errors))?;
+
+    let record = &NewCollectionRecord {
+        name: &name,
+        description: &update_collection
+            .description
+            .unwrap_or(collection.description.to_string()),
+        public: &update_collection.public.unwrap_or(collection.public),
+    };
+    db::collections::update(&conn, &collection.id, record)
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|c| json!({ "collection": c }))
+}
+
+#[delete("/collections/<id>")]
+pub fn delete_collections(
+    conn: db::Conn,
+    token: TokenData,
+    id: String,
+) -> Result<JsonValue, TentechError> {
+    let collection = find_own_collection(&conn, &token, &id)?;
+    db::collections::delete(&conn, &collection.id)
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|_| json!({}))
+}
+
+#[get("/collections/<id>")]
+pub fn get(
+    conn: db::Conn,
+    token: Option<TokenData>,
+    id: String,
+) -> Result<JsonValue, TentechError> {
+    let collection = find_collection(&conn, &id)?;
+    let user_id = token.map(|t| t.user.id);
+    if !collection.is_visible_to(user_id) {
+        return Err(TentechError::NotFound("Collection not found".to_string()));
+    }
+    db::collections::find_products(&conn, &collection.id)
+        .and_then(|ps| listing(&conn, &ps, user_id))
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|ps| json!({ "collection": collection, "products": ps }))
+}
+
+#[get("/users/<user_id>/collections")]
+pub fn get_by_user_id(
+    conn: db::Conn,
+    token: Option<TokenData>,
+    user_id: i32,
+) -> Result<JsonValue, TentechError> {
+    let is_owner = token.map_or(false, |t| t.user.id == user_id);
+    db::collections::find_by_user_id(&conn, &user_id, is_owner)
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|cs| json!({ "collections": cs }))
+}
+
+#[post("/collections/<id>/items", format = "json", data = "<new_item>")]
+pub fn add_item(
+    new_item: Json<NewItem>,
+    conn: db::Conn,
+    token: TokenData,
+    id: String,
+) -> Result<JsonValue, TentechError> {
+    let collection = find_own_collection(&conn, &token, &id)?;
+    let product = find_product(&conn, &new_item.into_inner().item.product_id)?;
+    check_visible(&conn, &product, Some(token.user.id))?;
+    db::collections::add_item(&conn, &collection.id, &product.id)
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|_| json!({}))
+}
+
+/// Removes an item, given by the id of its product or by its own id. The
+/// product is not looked up, so that hidden or trashed ones can be removed too.
+#[delete("/collections/<id>/items/<item>")]
+pub fn remove_item(
+    conn: db::Conn,
+    token: TokenData,
+    id: String,
+    item: String,
+) -> Result<JsonValue, TentechError> {
+    let collection = find_own_collection(&conn, &token, &id)?;
+    let removed = if let Ok(item_id) = item.parse::<i32>() {
+        db::collections::remove_item(&conn, &collection.id, &item_id)
+    } else if let Ok(uuid) = Uuid::parse_str(&item) {
+        db::collections::remove_product(&conn, &collection.id, &uuid)
+    } else {
+        return Err(TentechError::NotFound("Item not found".to_string()));
+    };
+    removed
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|_| json!({}))
+}
+
+#[put("/collections/<id>/items", format = "json", data = "<order>")]
+pub fn reorder_items(
+    order: Json<ItemOrder>,
+    conn: db::Conn,
+    token: TokenData,
+    id: String,
+) -> Result<JsonValue, TentechError> {
+    let collection = find_own_collection(&conn, &token, &id)?;
+    let invalid_order = || {
+        field_error(
+            "product_ids",
+            "invalid_order",
+            "Order must list every shown item of the collection once",
+        )
+    };
+    // Only the items shown by GET can be ordered; hidden or trashed ones
+    // keep their order after them.
+    let visible = db::collections::find_products(&conn, &collection.id)
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))?;
+    let mut product_ids = order
+        .into_inner()
+        .product_ids
+        .iter()
+        .map(|id| {
+            visible
+                .iter()
+                .find(|p| p.uuid.to_string() == *id)
+                .map(|p| p.id)
+                .ok_or_else(invalid_order)
+        })
+        .collect::<Result<Vec<i32>, TentechError>>()?;
+    let mut requested = product_ids.clone();
+    requested.sort();
+    requested.dedup();
+    if requested.len() != product_ids.len() || requested.len() != visible.len() {
+        return Err(invalid_order());
+    }
+    let hidden: Vec<i32> = db::collections::find_product_ids(&conn, &collection.id)
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))?
+        .into_iter()
+        .filter(|id| !product_ids.contains(id))
+        .collect();
+    product_ids.extend(hidden);
+
+    db::collections::reorder(&conn, &collection.id, &product_ids)
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|_| json!({}))
+}
@@ -0,0 +1,196 @@
+use crate::db;
+use crate::error::TentechError;
+use crate::events::Broadcaster;
+use crate::models::comment::Comment;
+use crate::models::notification::COMMENT;
+use crate::models::user::TokenData;
+use crate::routes::notifications::notify;
+use crate::routes::products::{check_visible, find_product, find_product_by_id};
+use crate::validation::{field_error, FieldValidator};
+use diesel::pg::PgConnection;
+use diesel::result::Error;
+use rocket::State;
+use rocket_contrib::json::{Json, JsonValue};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use validator::Validate;
+
+#[derive(Deserialize)]
+pub struct NewComment {
+    comment: NewCommentData,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct NewCommentData {
+    #[validate(length(min = "1", max = "2000"))]
+    body: Option<String>,
+    parent_id: Option<i32>,
+}
+
+fn find_comment(conn: &PgConnection, id: &i32) -> Result<Comment, TentechError> {
+    db::comments::find(conn, id).map_err(|e| match e {
+        Error::NotFound => TentechError::NotFound("Comment not found".to_string()),
+        e => TentechError::DatabaseFailed(format!("{}", e)),
+    })
+}
+
+/// Nests replies under their parent comment, attaching the author of each.
+fn threads(conn: &PgConnection, comments: Vec<Comment>) -> Result<Vec<Value>, Error> {
+    let user_ids: Vec<i32> = comments.iter().map(|c| c.user_id).collect();
+    let users = db::users::find_by_ids(conn, &user_ids)?;
+    let to_json = |c: &Comment| {
+        let user = users
+            .iter()
+            .find(|u| u.id == c.user_id)
+            .map(|u| u.to_public());
+        let mut json_comment = json!(c.clone().hide_deleted())
+            .as_object_mut()
+            .unwrap()
+            .clone();
+        json_comment.insert("user".to_string(), json!(user).into());
+        json_comment
+    };
+    Ok(comments
+        .iter()
+        .filter(|c| c.parent_id.is_none())
+        .map(|c| {
+            let replies: Vec<Map<String, Value>> = comments
+                .iter()
+                .filter(|r| r.parent_id == Some(c.id))
+                .map(|r| to_json(r))
+                .collect();
+            let mut json_comment = to_json(c);
+            json_comment.insert("replies".to_string(), json!(replies).into());
+            Value::Object(json_comment)
+        })
+        .collect())
+}
+
+#[get("/products/<id>/comments")]
+pub fn get_by_product_id(
+    conn: db::Conn,
+    token: Option<TokenData>,
+    id: String,
+) -> Result<JsonValue, TentechError> {
+    let product = find_product(&conn, &id)?;
+    check_visible(&conn, &product, token.map(|t| t.user.id))?;
+    db::comments::find_by_product_id(&conn, &product.id)
+        .and_then(|cs| threads(&conn, cs))
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|cs| json!({ "comments": cs }))
+}
+
+#[post("/products/<id>/comments", format = "json", data = "<new_comment>")]
+pub fn post_comments(
+    new_comment: Json<NewComment>,
+    conn: db::Conn,
+    broadcaster: State<Broadcaster>,
+    token: TokenData,
+    id: String,
+) -> Result<JsonValue, TentechError> {
+    let product = find_product(&conn, &id)?;
+    check_visible(&conn, &product, Some(token.user.id))?;
+    let new_comment = new_comment.into_inner().comment;
+
+    let mut extractor = FieldValidator::validate(&new_comment);
+    let body = extractor.extract("body", new_comment.body);
+
+    extractor
+        .check()
+        .map_err(|e| TentechError::ValidationFailed(e.errors))?;
+
+    // Only one level of replies: a reply has to point at a top level comment.
+    if let Some(parent_id) = new_comment.parent_id {
+        let parent = find_comment(&conn, &parent_id)?;
+        if parent.product_id != product.id {
+            return Err(field_error(
+                "parent_id",
+                "invalid_parent",
+                "Parent comment belongs to another product",
+            ));
+        }
+        if parent.parent_id.is_some() {
+            return Err(field_error(
+                "parent_id",
+                "invalid_parent",
+                "Cannot reply to a reply",
+            ));
+        }
+    }
+
+    db::comments::create(
+        &conn,
+        &product.id,
+        &token.user.id,
+        new_comment.parent_id.as_ref(),
+        &body,
+    )
+    .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+    .map(|c| {
+        notify(
+            &conn,
+            &broadcaster,
+            &product.user_id,
+            COMMENT,
+            Some(&product.id),
+            "",
+            &token.user.id,
+        );
+        json!({ "comment": c })
+    })
+}
+
+#[patch("/comments/<id>", format = "json", data = "<update_comment>")]
+pub fn update_comments(
+    update_comment: Json<NewComment>,
+    conn: db::Conn,
+    token: TokenData,
+    id: i32,
+) -> Result<JsonValue, TentechError> {
+    let comment = find_comment(&conn, &id)?;
+    if comment.is_deleted() {
+        return Err(TentechError::NotFound("Comment not found".to_string()));
+    }
+    // Comments of a trashed or hidden product cannot be edited either.
+    let product = find_product_by_id(&conn, &comment.product_id)?;
+    check_visible(&conn, &product, Some(token.user.id))?;
+    if !(comment.user_id == token.user.id) {
+        return Err(TentechError::Unauthorized(
+            "Cannot edit other's comment".to_string(),
+        ));
+    }
+    let update_comment = update_comment.into_inner().comment;
+
+    let mut extractor = FieldValidator::validate(&update_comment);
+    let body = extractor.extract("body", update_comment.body);
+
+    extractor
+        .check()
+        .map_err(|e| TentechError::ValidationFailed(e.errors))?;
+
+    db::comments::update(&conn, &comment.id, &body)
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|c| json!({ "comment": c }))
+}
+
+#[delete("/comments/<id>")]
+pub fn delete_comments(
+    conn: db::Conn,
+    token: TokenData,
+    id: i32,
+) -> Result<JsonValue, TentechError> {
+    let comment = find_comment(&conn, &id)?;
+    if comment.is_deleted() {
+        return Err(TentechError::NotFound("Comment not found".to_string()));
+    }
+    // The author and the owner of the product can both remove a comment.
+    let product = find_product_by_id(&conn, &comment.product_id)?;
+    if !(comment.user_id == token.user.id || product.user_id == token.user.id) {
+        return Err(TentechError::Unauthorized(
+            "Cannot delete other's comment".to_string(),
+        ));
+    }
+    db::comments::delete(&conn, &comment.id)
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|c| json!({ "comment": c.hide_deleted() }))
+}
@@ -0,0 +1,164 @@
+use crate::db;
+use crate::db::devlogs::NewDevlog as NewDevlogRecord;
+use crate::error::TentechError;
+use crate::models::devlog::Devlog;
+use crate::models::product::Product;
+use crate::models::user::TokenData;
+use crate::routes::products::{check_visible, find_product, find_product_by_id, member_of};
+use crate::validation::{field_error, FieldValidator};
+use diesel::pg::PgConnection;
+use diesel::result::Error;
+use rocket_contrib::json::{Json, JsonValue};
+use serde::Deserialize;
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Deserialize)]
+pub struct NewDevlog {
+    devlog: NewDevlogData,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct NewDevlogData {
+    #[validate(length(min = "1", max = "10000"))]
+    body: Option<String>,
+    hours: Option<i32>,
+    #[validate(url)]
+    img: Option<String>,
+}
+
+/// Looks up the entry addressed by `id`, answering 404 for malformed or unknown ids.
+fn find_devlog(conn: &PgConnection, id: &str) -> Result<Devlog, TentechError> {
+    let not_found = || TentechError::NotFound("Devlog not found".to_string());
+    let uuid = Uuid::parse_str(id).map_err(|_| not_found())?;
+    db::devlogs::find(conn, &uuid).map_err(|e| match e {
+        Error::NotFound => not_found(),
+        e => TentechError::DatabaseFailed(format!("{}", e)),
+    })
+}
+
+fn check_editable(
+    conn: &PgConnection,
+    token: &TokenData,
+    product: &Product,
+) -> Result<(), TentechError> {
+    if !member_of(conn, product, Some(token.user.id))?.map_or(false, |m| m.can_edit()) {
+        return Err(TentechError::Unauthorized(
+            "Cannot write the devlog of other's product".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn check_hours(hours: &Option<i32>) -> Result<(), TentechError> {
+    if hours.map_or(false, |h| h < 0) {
+        return Err(field_error(
+            "hours",
+            "invalid_hours",
+            "Hours must not be negative",
+        ));
+    }
+    Ok(())
+}
+
+#[get("/products/<id>/devlogs")]
+pub fn get_by_product_id(
+    conn: db::Conn,
+    token: Option<TokenData>,
+    id: String,
+) -> Result<JsonValue, TentechError> {
+    let product = find_product(&conn, &id)?;
+    check_visible(&conn, &product, token.map(|t| t.user.id))?;
+    db::devlogs::find_by_product_id(&conn, &product.id)
+        .and_then(|ds| {
+            let user_ids: Vec<i32> = ds.iter().map(|d| d.user_id).collect();
+            let users = db::users::find_by_ids(&conn, &user_ids)?;
+            let devlogs: Vec<_> = ds
+                .iter()
+                .map(|d| {
+                    let user = users
+                        .iter()
+                        .find(|u| u.id == d.user_id)
+                        .map(|u| u.to_public());
+                    let mut json_devlog = json!(d).as_object_mut().unwrap().clone();
+                    json_devlog.insert("user".to_string(), json!(user).into());
+                    json_devlog
+                })
+                .collect();
+            Ok(json!({ "devlogs": devlogs }))
+        })
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+}
+
+#[post("/products/<id>/devlogs", format = "json", data = "<new_devlog>")]
+pub fn post_devlogs(
+    new_devlog: Json<NewDevlog>,
+    conn: db::Conn,
+    token: TokenData,
+    id: String,
+) -> Result<JsonValue, TentechError> {
+    let product = find_product(&conn, &id)?;
+    check_editable(&conn, &token, &product)?;
+    let new_devlog = new_devlog.into_inner().devlog;
+
+    let mut extractor = FieldValidator::validate(&new_devlog);
+    let body = extractor.extract("body", new_devlog.body);
+
+    extractor
+        .check()
+        .map_err(|e| TentechError::ValidationFailed(e.errors))?;
+    check_hours(&new_devlog.hours)?;
+
+    let record = &NewDevlogRecord {
+        body: &body,
+        hours: new_devlog.hours.as_ref(),
+        img: new_devlog.img.as_ref().map(String::as_str),
+    };
+    db::devlogs::create(&conn, &product.id, &token.user.id, record)
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|d| json!({ "devlog": d }))
+}
+
+#[patch("/devlogs/<id>", format = "json", data = "<update_devlog>")]
+pub fn update_devlogs(
+    update_devlog: Json<NewDevlog>,
+    conn: db::Conn,
+    token: TokenData,
+    id: String,
+) -> Result<JsonValue, TentechError> {
+    let devlog = find_devlog(&conn, &id)?;
+    let product = find_product_by_id(&conn, &devlog.product_id)?;
+    check_editable(&conn, &token, &product)?;
+    let update_devlog = update_devlog.into_inner().devlog;
+
+    let mut extractor = FieldValidator::validate(&update_devlog);
+    let body = extractor.extract("body", update_devlog.body);
+
+    extractor
+        .check()
+        .map_err(|e| TentechError::ValidationFailed(e.errors))?;
+    check_hours(&update_devlog.hours)?;
+
+    let record = &NewDevlogRecord {
+        body: &body,
+        hours: update_devlog.hours.as_ref(),
+        img: update_devlog.img.as_ref().map(String::as_str),
+    };
+    db::devlogs::update(&conn, &devlog.id, record)
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|d| json!({ "devlog": d }))
+}
+
+#[delete("/devlogs/<id>")]
+pub fn delete_devlogs(
+    conn: db::Conn,
+    token: TokenData,
+    id: String,
+) -> Result<JsonValue, TentechError> {
+    let devlog = find_devlog(&conn, &id)?;
+    let product = find_product_by_id(&conn, &devlog.product_id)?;
+    check_editable(&conn, &token, &product)?;
+    db::devlogs::delete(&conn, &devlog)
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|_| json!({}))
+}
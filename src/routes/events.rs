@@ -0,0 +1,82 @@
+use crate::db;
+use crate::error::TentechError;
+use crate::events::{Broadcaster, Event, EventStream, Topic};
+use crate::models::product::Product;
+use crate::models::user::TokenData;
+use crate::routes::products::{check_visible, find_product};
+use diesel::pg::PgConnection;
+use rocket::http::ContentType;
+use rocket::response::{Content, Stream};
+use rocket::State;
+
+/// Tells everyone about a product which has just been published.
+pub fn product_published(broadcaster: &Broadcaster, product: &Product) {
+    broadcaster.publish(Event::new(
+        Topic::Global,
+        "product.published",
+        json!({ "product": product }).into(),
+    ));
+}
+
+/// Tells the subscribers of the product its new reaction counts.
+pub fn reactions_changed(conn: &PgConnection, broadcaster: &Broadcaster, product: &Product) {
+    match db::reactions::count_by_product_ids(conn, &vec![product.id]) {
+        Ok(counts) => broadcaster.publish(Event::new(
+            Topic::Product(product.uuid),
+            "reactions",
+            json!({
+                "product_id": product.uuid,
+                "reactions": counts.get(&product.id).cloned().unwrap_or_default(),
+            })
+            .into(),
+        )),
+        Err(e) => println!("Could not count reactions of {}: {}", product.uuid, e),
+    }
+}
+
+/// Streams server-sent events of the requested topics: `global` for newly
+/// published products, `products` (comma separated ids) for their reaction
+/// counts, and `user` for the notifications of the signed in user. Answers
+/// 503 once `EVENT_STREAMS_MAX` streams are open.
+#[get("/events?<global>&<products>&<user>")]
+pub fn stream(
+    conn: db::Conn,
+    broadcaster: State<Broadcaster>,
+    token: Option<TokenData>,
+    global: Option<bool>,
+    products: Option<String>,
+    user: Option<bool>,
+) -> Result<Content<Stream<EventStream>>, TentechError> {
+    let user_id = token.map(|t| t.user.id);
+    let mut topics = Vec::new();
+    if global.unwrap_or(false) {
+        topics.push(Topic::Global);
+    }
+    for id in products.iter().flat_map(|ps| ps.split(',')) {
+        let product = find_product(&conn, id.trim())?;
+        check_visible(&conn, &product, user_id)?;
+        topics.push(Topic::Product(product.uuid));
+    }
+    if user.unwrap_or(false) {
+        match user_id {
+            Some(id) => topics.push(Topic::User(id)),
+            None => {
+                return Err(TentechError::Unauthorized(
+                    "Sign in to receive notifications".to_string(),
+                ))
+            }
+        }
+    }
+    if topics.is_empty() {
+        topics.push(Topic::Global);
+    }
+    Ok(Content(
+        ContentType::new("text", "event-stream"),
+        Stream::chunked(
+            broadcaster
+                .subscribe(topics)
+                .ok_or(TentechError::TooManyStreams)?,
+            4096,
+        ),
+    ))
+}
@@ -0,0 +1,218 @@
+use crate::db;
+use crate::db::media::NewMedia as NewMediaRecord;
+use crate::error::TentechError;
+use crate::models::media::{Media, IMAGE, VIDEO};
+use crate::models::product::Product;
+use crate::models::user::TokenData;
+use crate::routes::products::{check_visible, find_product, member_of};
+use crate::s3;
+use crate::validation::{field_error, FieldValidator};
+use diesel::pg::PgConnection;
+use diesel::result::Error;
+use diesel::Connection;
+use rocket::State;
+use rocket_contrib::json::{Json, JsonValue};
+use rusoto_s3::S3Client;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Deserialize)]
+pub struct NewMedia {
+    media: NewMediaData,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct NewMediaData {
+    kind: Option<String>,
+    #[validate(length(min = "1", max = "255"))]
+    storage_key: Option<String>,
+    #[validate(length(max = "200"))]
+    caption: Option<String>,
+    #[validate(length(max = "200"))]
+    alt: Option<String>,
+    width: Option<i32>,
+    height: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct MediaOrder {
+    media_ids: Vec<String>,
+}
+
+fn find_editable_product(
+    conn: &PgConnection,
+    token: &TokenData,
+    id: &str,
+) -> Result<Product, TentechError> {
+    let product = find_product(conn, id)?;
+    if !member_of(conn, &product, Some(token.user.id))?.map_or(false, |m| m.can_edit()) {
+        return Err(TentechError::Unauthorized(
+            "Cannot change other's product".to_string(),
+        ));
+    }
+    Ok(product)
+}
+
+/// Looks up the media addressed by `id` within the product, answering 404 for malformed or unknown ids.
+fn find_media(conn: &PgConnection, product: &Product, id: &str) -> Result<Media, TentechError> {
+    let not_found = || TentechError::NotFound("Media not found".to_string());
+    let uuid = Uuid::parse_str(id).map_err(|_| not_found())?;
+    db::media::find(conn, &product.id, &uuid).map_err(|e| match e {
+        Error::NotFound => not_found(),
+        e => TentechError::DatabaseFailed(format!("{}", e)),
+    })
+}
+
+/// Serializes the media together with the public url of its stored object.
+pub fn media_json(media: &Media) -> Map<String, Value> {
+    let mut json_media = json!(media).as_object_mut().unwrap().clone();
+    json_media.insert(
+        "url".to_string(),
+        json!(s3::object_url(&media.storage_key)).into(),
+    );
+    json_media
+}
+
+#[get("/products/<id>/media")]
+pub fn get_by_product_id(
+    conn: db::Conn,
+    token: Option<TokenData>,
+    id: String,
+) -> Result<JsonValue, TentechError> {
+    let product = find_product(&conn, &id)?;
+    check_visible(&conn, &product, token.map(|t| t.user.id))?;
+    db::media::find_by_product_id(&conn, &product.id)
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|ms| {
+            let media: Vec<_> = ms.iter().map(media_json).collect();
+            json!({ "media": media })
+        })
+}
+
+#[post("/products/<id>/media", format = "json", data = "<new_media>")]
+pub fn post_media(
+    new_media: Json<NewMedia>,
+    conn: db::Conn,
+    token: TokenData,
+    id: String,
+) -> Result<JsonValue, TentechError> {
+    let product = find_editable_product(&conn, &token, &id)?;
+    let new_media = new_media.into_inner().media;
+
+    let mut extractor = FieldValidator::validate(&new_media);
+    let kind = extractor.extract("kind", new_media.kind);
+    let storage_key = extractor.extract("storage_key", new_media.storage_key);
+    let width = extractor.extract("width", new_media.width);
+    let height = extractor.extract("height", new_media.height);
+
+    extractor
+        .check()
+        .map_err(|e| TentechError::ValidationFailed(e.errors))?;
+
+    if !(kind == IMAGE || kind == VIDEO) {
+        return Err(field_error(
+            "kind",
+            "unknown_kind",
+            "Kind must be image or video",
+        ));
+    }
+    if width < 1 || height < 1 {
+        return Err(field_error(
+            "size",
+            "invalid_size",
+            "Width and height must be positive",
+        ));
+    }
+    if !s3::is_owned_by(&storage_key, &token.user.id) {
+        return Err(field_error(
+            "storage_key",
+            "not_owned",
+            "Storage key must come from your own upload",
+        ));
+    }
+    // A stored object belongs to one media only, so removing it never breaks another gallery.
+    match db::media::find_by_storage_key(&conn, &storage_key) {
+        Ok(_) => {
+            return Err(field_error(
+                "storage_key",
+                "taken",
+                "Storage key is already used",
+            ))
+        }
+        Err(Error::NotFound) => {}
+        Err(e) => return Err(TentechError::DatabaseFailed(format!("{}", e))),
+    }
+
+    let record = &NewMediaRecord {
+        kind: &kind,
+        storage_key: &storage_key,
+        caption: &new_media.caption.unwrap_or_default(),
+        alt: &new_media.alt.unwrap_or_default(),
+        width: &width,
+        height: &height,
+        user_id: Some(&token.user.id),
+    };
+    db::media::create(&conn, &product.id, record)
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|m| json!({ "media": media_json(&m) }))
+}
+
+#[put("/products/<id>/media", format = "json", data = "<order>")]
+pub fn reorder_media(
+    order: Json<MediaOrder>,
+    conn: db::Conn,
+    token: TokenData,
+    id: String,
+) -> Result<JsonValue, TentechError> {
+    let product = find_editable_product(&conn, &token, &id)?;
+    let ids = order
+        .into_inner()
+        .media_ids
+        .iter()
+        .map(|id| find_media(&conn, &product, id).map(|m| m.id))
+        .collect::<Result<Vec<i32>, TentechError>>()?;
+
+    // The new order has to mention every media exactly once.
+    let mut current: Vec<i32> = db::media::find_by_product_id(&conn, &product.id)
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))?
+        .iter()
+        .map(|m| m.id)
+        .collect();
+    let mut requested = ids.clone();
+    current.sort();
+    requested.sort();
+    if current != requested {
+        return Err(field_error(
+            "media_ids",
+            "invalid_order",
+            "Order must list every media of the product once",
+        ));
+    }
+
+    db::media::reorder(&conn, &product.id, &ids)
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|_| json!({}))
+}
+
+#[delete("/products/<id>/media/<media_id>")]
+pub fn delete_media(
+    conn: db::Conn,
+    token: TokenData,
+    client: State<S3Client>,
+    id: String,
+    media_id: String,
+) -> Result<JsonValue, TentechError> {
+    let product = find_editable_product(&conn, &token, &id)?;
+    let media = find_media(&conn, &product, &media_id)?;
+    // Keep the row when the stored object cannot be removed, so that it can be retried.
+    conn.transaction::<_, TentechError, _>(|| {
+        db::media::delete(&conn, &media.id)?;
+        if s3::can_delete(&media) {
+            s3::delete_object(&client, &media.storage_key)?;
+        }
+        Ok(())
+    })
+    .map(|_| json!({}))
+}
@@ -0,0 +1,130 @@
+use crate::db;
+use crate::error::TentechError;
+use crate::events::Broadcaster;
+use crate::models::notification::INVITE;
+use crate::models::user::TokenData;
+use crate::routes::notifications::notify;
+use crate::routes::products::{check_visible, find_product, member_of};
+use diesel::result::Error;
+use rocket::State;
+use rocket_contrib::json::{Json, JsonValue};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct NewMember {
+    member: NewMemberData,
+}
+
+#[derive(Deserialize)]
+pub struct NewMemberData {
+    username: String,
+}
+
+#[get("/products/<id>/members")]
+pub fn get_by_product_id(
+    conn: db::Conn,
+    token: Option<TokenData>,
+    id: String,
+) -> Result<JsonValue, TentechError> {
+    let product = find_product(&conn, &id)?;
+    check_visible(&conn, &product, token.map(|t| t.user.id))?;
+    db::members::find_by_product_id(&conn, &product.id)
+        .and_then(|ms| {
+            let user_ids: Vec<i32> = ms.iter().map(|m| m.user_id).collect();
+            let users = db::users::find_by_ids(&conn, &user_ids)?;
+            let members: Vec<_> = ms
+                .iter()
+                .map(|m| {
+                    let user = users
+                        .iter()
+                        .find(|u| u.id == m.user_id)
+                        .map(|u| u.to_public());
+                    let mut json_member = json!(m).as_object_mut().unwrap().clone();
+                    json_member.insert("user".to_string(), json!(user).into());
+                    json_member
+                })
+                .collect();
+            Ok(json!({ "members": members }))
+        })
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+}
+
+#[post("/products/<id>/members", format = "json", data = "<new_member>")]
+pub fn invite(
+    new_member: Json<NewMember>,
+    conn: db::Conn,
+    broadcaster: State<Broadcaster>,
+    token: TokenData,
+    id: String,
+) -> Result<JsonValue, TentechError> {
+    let product = find_product(&conn, &id)?;
+    if !member_of(&conn, &product, Some(token.user.id))?.map_or(false, |m| m.is_owner()) {
+        return Err(TentechError::Unauthorized(
+            "Only the owner can invite members".to_string(),
+        ));
+    }
+    let new_member = new_member.into_inner().member;
+    let user = db::users::find_by_username(&conn, &new_member.username).map_err(|e| match e {
+        Error::NotFound => TentechError::NotFound("User not found".to_string()),
+        e => TentechError::DatabaseFailed(format!("{}", e)),
+    })?;
+    match db::members::find(&conn, &product.id, &user.id) {
+        Ok(_) => return Err(TentechError::AlreadyMember),
+        Err(Error::NotFound) => {}
+        Err(e) => return Err(TentechError::DatabaseFailed(format!("{}", e))),
+    }
+    db::members::invite(&conn, &product.id, &user.id, &token.user.id)
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|m| {
+            notify(
+                &conn,
+                &broadcaster,
+                &user.id,
+                INVITE,
+                Some(&product.id),
+                "",
+                &token.user.id,
+            );
+            json!({ "member": m })
+        })
+}
+
+#[post("/products/<id>/members/accept")]
+pub fn accept(conn: db::Conn, token: TokenData, id: String) -> Result<JsonValue, TentechError> {
+    let product = find_product(&conn, &id)?;
+    db::members::accept(&conn, &product.id, &token.user.id)
+        .map_err(|e| match e {
+            Error::NotFound => TentechError::NotFound("Invitation not found".to_string()),
+            e => TentechError::DatabaseFailed(format!("{}", e)),
+        })
+        .map(|m| json!({ "member": m }))
+}
+
+#[delete("/products/<id>/members/<user_id>")]
+pub fn remove(
+    conn: db::Conn,
+    token: TokenData,
+    id: String,
+    user_id: i32,
+) -> Result<JsonValue, TentechError> {
+    let product = find_product(&conn, &id)?;
+    let target = db::members::find(&conn, &product.id, &user_id).map_err(|e| match e {
+        Error::NotFound => TentechError::NotFound("Member not found".to_string()),
+        e => TentechError::DatabaseFailed(format!("{}", e)),
+    })?;
+    if target.is_owner() {
+        return Err(TentechError::Unauthorized(
+            "Cannot remove the owner".to_string(),
+        ));
+    }
+    // Members can leave (or decline an invitation) on their own.
+    let is_owner = member_of(&conn, &product, Some(token.user.id))?.map_or(false, |m| m.is_owner());
+    if !(is_owner || target.user_id == token.user.id) {
+        return Err(TentechError::Unauthorized(
+            "Cannot remove other members".to_string(),
+        ));
+    }
+    db::members::remove(&conn, &product.id, &user_id)
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|_| json!({}))
+}
@@ -1,6 +1,17 @@
+pub mod bookmarks;
+pub mod collections;
+pub mod comments;
+pub mod devlogs;
+pub mod events;
+pub mod media;
+pub mod members;
+pub mod notifications;
 pub mod products;
 pub mod reactions;
+pub mod revisions;
 pub mod s3;
 pub mod suggestions;
 pub mod tags;
 pub mod users;
+pub mod views;
+pub mod webhooks;
@@ -0,0 +1,147 @@
+use crate::db;
+use crate::error::TentechError;
+use crate::events::{Broadcaster, Event, Topic};
+use crate::models::notification::{message, Notification, REACTION};
+use crate::models::user::TokenData;
+use crate::routes::products::paginate;
+use diesel::pg::PgConnection;
+use diesel::result::Error;
+use rocket_contrib::json::JsonValue;
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+/// How many of the latest actors are listed in a notification, while its
+/// message names two at most.
+const NAMED_ACTORS: usize = 3;
+
+/// Notifies the user about an action which has already succeeded, so a
+/// failure is only logged instead of failing the request. Streams of the user
+/// learn the new unread count.
+pub fn notify(
+    conn: &PgConnection,
+    broadcaster: &Broadcaster,
+    user_id: &i32,
+    kind: &str,
+    product_id: Option<&i32>,
+    subject: &str,
+    actor_id: &i32,
+) {
+    let notified = db::notifications::notify(conn, user_id, kind, product_id, subject, actor_id)
+        .and_then(|_| db::notifications::unread_count(conn, user_id));
+    match notified {
+        Ok(count) => broadcaster.publish(Event::new(
+            Topic::User(*user_id),
+            "notification",
+            json!({ "unread_count": count }).into(),
+        )),
+        Err(e) => println!("Could not notify user {}: {}", user_id, e),
+    }
+}
+
+/// Looks up the notification of the user addressed by `id`, answering 404 for malformed or unknown ids.
+fn find_notification(
+    conn: &PgConnection,
+    token: &TokenData,
+    id: &str,
+) -> Result<Notification, TentechError> {
+    let not_found = || TentechError::NotFound("Notification not found".to_string());
+    let uuid = Uuid::parse_str(id).map_err(|_| not_found())?;
+    db::notifications::find(conn, &token.user.id, &uuid).map_err(|e| match e {
+        Error::NotFound => not_found(),
+        e => TentechError::DatabaseFailed(format!("{}", e)),
+    })
+}
+
+fn notifications_json(
+    conn: &PgConnection,
+    ns: &[Notification],
+) -> Result<Vec<Map<String, Value>>, Error> {
+    let user_ids: Vec<i32> = ns
+        .iter()
+        .flat_map(|n| n.actor_ids.iter().take(NAMED_ACTORS).cloned())
+        .collect();
+    let users = db::users::find_by_ids(conn, &user_ids)?;
+    let product_ids: Vec<i32> = ns.iter().filter_map(|n| n.product_id).collect();
+    let products = db::products::find_by_ids(conn, &product_ids)?;
+    let kinds = db::reactions::kinds(conn)?;
+    Ok(ns
+        .iter()
+        .map(|n| {
+            let actors: Vec<_> = n
+                .actor_ids
+                .iter()
+                .take(NAMED_ACTORS)
+                .filter_map(|id| users.iter().find(|u| u.id == *id))
+                .map(|u| u.to_public())
+                .collect();
+            let names: Vec<&str> = actors.iter().map(|u| u.nickname.as_str()).collect();
+            let product = products.iter().find(|p| Some(p.id) == n.product_id);
+            let title = product.map_or("a deleted product", |p| p.title.as_str());
+            let subject = match kinds
+                .iter()
+                .find(|k| n.kind == REACTION && k.name == n.subject)
+            {
+                Some(k) => k.emoji.as_str(),
+                None => n.subject.as_str(),
+            };
+            let mut json_notification = json!(n).as_object_mut().unwrap().clone();
+            json_notification.remove("actor_ids");
+            json_notification.insert(
+                "message".to_string(),
+                json!(message(&n.kind, &names, n.actor_ids.len(), subject, title)).into(),
+            );
+            json_notification.insert("actor_count".to_string(), json!(n.actor_ids.len()).into());
+            json_notification.insert("actors".to_string(), json!(actors).into());
+            json_notification.insert(
+                "product".to_string(),
+                json!(product.map(|p| json!({
+                    "id": p.uuid,
+                    "title": p.title,
+                    "slug": p.slug,
+                    "img": p.img,
+                })))
+                .into(),
+            );
+            json_notification
+        })
+        .collect())
+}
+
+#[get("/notifications?<page>&<per_page>")]
+pub fn get_notifications(
+    conn: db::Conn,
+    token: TokenData,
+    page: Option<i64>,
+    per_page: Option<i64>,
+) -> Result<JsonValue, TentechError> {
+    let (page, per_page) = paginate(page, per_page);
+    db::notifications::find_by_user_id(&conn, &token.user.id, page - 1, per_page)
+        .and_then(|ns| notifications_json(&conn, &ns))
+        .map(|ns| json!({ "notifications": ns, "page": page, "per_page": per_page }))
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+}
+
+#[get("/notifications/unread_count")]
+pub fn unread_count(conn: db::Conn, token: TokenData) -> Result<JsonValue, TentechError> {
+    db::notifications::unread_count(&conn, &token.user.id)
+        .map(|count| json!({ "count": count }))
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+}
+
+#[post("/notifications/<id>/read")]
+pub fn mark_read(conn: db::Conn, token: TokenData, id: String) -> Result<JsonValue, TentechError> {
+    let notification = find_notification(&conn, &token, &id)?;
+    if notification.is_read() {
+        return Ok(json!({}));
+    }
+    db::notifications::mark_read(&conn, &notification.id)
+        .map(|_| json!({}))
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+}
+
+#[post("/notifications/read")]
+pub fn mark_all_read(conn: db::Conn, token: TokenData) -> Result<JsonValue, TentechError> {
+    db::notifications::mark_all_read(&conn, &token.user.id)
+        .map(|count| json!({ "count": count }))
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+}
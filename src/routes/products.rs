@@ -1,14 +1,29 @@
 use crate::db;
 use crate::error::TentechError;
+use crate::events::Broadcaster;
+use crate::models::link::{Link, DEMO, DOCS, OTHER, REPO};
+use crate::models::member::Member;
+use crate::models::product::{Product, ARCHIVED, DRAFT, PUBLISHED};
 use crate::models::user::TokenData;
-use crate::validation::FieldValidator;
+use crate::models::view::Visit;
+use crate::models::webhook::{PRODUCT_CREATED, PRODUCT_UPDATED};
+use crate::routes::events::product_published;
+use crate::routes::media::media_json;
+use crate::routes::webhooks::trigger_product;
+use crate::trash;
+use crate::validation::{field_error, FieldValidator};
+use diesel::pg::PgConnection;
+use diesel::result::Error;
 use percent_encoding::percent_decode_str;
+use rocket::response::Redirect;
+use rocket::State;
 use rocket_contrib::json::{Json, JsonValue};
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value};
 use std::collections::HashMap;
 use std::vec::Vec;
 use uuid::Uuid;
-use validator::Validate;
+use validator::{validate_url, Validate};
 
 #[derive(Deserialize)]
 pub struct NewProduct {
@@ -26,18 +41,185 @@ pub struct NewProductData {
     #[validate(url)]
     img: Option<String>,
     duration: i32,
+    duration_from_devlogs: Option<bool>,
     kind: String,
     status: String,
     tags: Vec<i32>,
+    publish: Option<bool>,
+    /// `None` when omitted, `Some(None)` when explicitly `null`.
+    #[serde(default, deserialize_with = "explicit")]
+    inspired_by: Option<Option<String>>,
+    /// Links are left as they are when omitted, and removed by `null` or "".
+    #[serde(default, deserialize_with = "explicit")]
+    repo_url: Option<Option<String>>,
+    #[serde(default, deserialize_with = "explicit")]
+    demo_url: Option<Option<String>>,
+    #[serde(default, deserialize_with = "explicit")]
+    docs_url: Option<Option<String>>,
+    #[serde(default, deserialize_with = "explicit")]
+    other_url: Option<Option<String>>,
+}
+
+impl NewProductData {
+    /// The link fields with the kind of link each of them holds.
+    fn link_fields(&self) -> Vec<(&'static str, &'static str, &Option<Option<String>>)> {
+        vec![
+            ("repo_url", REPO, &self.repo_url),
+            ("demo_url", DEMO, &self.demo_url),
+            ("docs_url", DOCS, &self.docs_url),
+            ("other_url", OTHER, &self.other_url),
+        ]
+    }
+
+    /// Whether any link field was sent, in which case the links have to be stored.
+    fn has_links(&self) -> bool {
+        self.link_fields().iter().any(|(_, _, url)| url.is_some())
+    }
+
+    fn check_links(&self) -> Result<(), TentechError> {
+        for (field, _, url) in self.link_fields() {
+            if let Some(Some(url)) = url {
+                if !url.is_empty() && !validate_url(url.as_str()) {
+                    return Err(field_error(field, "url", "Url is invalid"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Pairs every link url with its kind, keeping the `current` links of the
+    /// fields which were left out.
+    fn links(&self, current: &[Link]) -> Vec<(String, String)> {
+        self.link_fields()
+            .into_iter()
+            .filter_map(|(_, kind, url)| {
+                let url = match url {
+                    Some(url) => url.clone().filter(|u| !u.is_empty()),
+                    None => current
+                        .iter()
+                        .find(|l| l.kind == kind)
+                        .map(|l| l.url.clone()),
+                };
+                url.map(|u| (kind.to_string(), u))
+            })
+            .collect()
+    }
+}
+
+/// Looks up the product addressed by `id`, answering 404 for malformed or unknown ids.
+pub fn find_product(conn: &PgConnection, id: &str) -> Result<Product, TentechError> {
+    let not_found = || TentechError::NotFound("Product not found".to_string());
+    let uuid = Uuid::parse_str(id).map_err(|_| not_found())?;
+    db::products::find(conn, &uuid).map_err(|e| match e {
+        Error::NotFound => not_found(),
+        e => TentechError::DatabaseFailed(format!("{}", e)),
+    })
+}
+
+/// Looks up the product a comment or devlog belongs to, which is gone once it
+/// has been moved to the trash.
+pub fn find_product_by_id(conn: &PgConnection, id: &i32) -> Result<Product, TentechError> {
+    db::products::find_by_id(conn, id).map_err(|e| match e {
+        Error::NotFound => TentechError::NotFound("Product not found".to_string()),
+        e => TentechError::DatabaseFailed(format!("{}", e)),
+    })
+}
+
+/// Looks up the product of `username` addressed by its current or a former slug.
+pub fn find_product_by_slug(
+    conn: &PgConnection,
+    username: &String,
+    slug: &str,
+    user_id: Option<i32>,
+) -> Result<Product, TentechError> {
+    let not_found = || TentechError::NotFound("Product not found".to_string());
+    let user = db::users::find_by_username(conn, username).map_err(|_| not_found())?;
+    let product = db::products::find_by_slug(conn, &user.id, slug)
+        .or_else(|e| match e {
+            Error::NotFound => db::products::find_by_old_slug(conn, &user.id, slug),
+            e => Err(e),
+        })
+        .map_err(|e| match e {
+            Error::NotFound => not_found(),
+            e => TentechError::DatabaseFailed(format!("{}", e)),
+        })?;
+    // Checked here so that an old slug never reveals a product the viewer cannot see.
+    check_visible(conn, &product, user_id)?;
+    Ok(product)
+}
+
+/// Returns the accepted membership of the user in the product, if any.
+pub fn member_of(
+    conn: &PgConnection,
+    product: &Product,
+    user_id: Option<i32>,
+) -> Result<Option<Member>, TentechError> {
+    match user_id {
+        Some(user_id) => db::members::find_accepted(conn, &product.id, &user_id)
+            .map_err(|e| TentechError::DatabaseFailed(format!("{}", e))),
+        None => Ok(None),
+    }
+}
+
+/// Drafts and archived products are only visible to their members.
+pub fn check_visible(
+    conn: &PgConnection,
+    product: &Product,
+    user_id: Option<i32>,
+) -> Result<(), TentechError> {
+    if product.is_published() || member_of(conn, product, user_id)?.is_some() {
+        Ok(())
+    } else {
+        Err(TentechError::NotFound("Product not found".to_string()))
+    }
+}
+
+/// Tells a field sent as `null` apart from one left out, which serde would
+/// otherwise both read as `None`.
+fn explicit<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Option<String>>, D::Error> {
+    Option::<String>::deserialize(d).map(Some)
+}
+
+/// Resolves the product given as inspiration, which has to be visible to the
+/// user and must not have been inspired by `product_id` itself. An empty one
+/// means no inspiration.
+fn resolve_inspired_by(
+    conn: &PgConnection,
+    inspired_by: &Option<String>,
+    product_id: Option<i32>,
+    user_id: &i32,
+) -> Result<Option<i32>, TentechError> {
+    let id = match inspired_by {
+        Some(id) if !id.is_empty() => id,
+        _ => return Ok(None),
+    };
+    let unknown = || field_error("inspired_by", "unknown_product", "Product not found");
+    let inspiration = find_product(conn, id).map_err(|_| unknown())?;
+    check_visible(conn, &inspiration, Some(*user_id)).map_err(|_| unknown())?;
+    if let Some(product_id) = product_id {
+        if db::products::is_derived_from(conn, &inspiration.id, &product_id)
+            .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))?
+        {
+            return Err(field_error(
+                "inspired_by",
+                "cycle",
+                "A product cannot be inspired by itself or by its descendants",
+            ));
+        }
+    }
+    Ok(Some(inspiration.id))
 }
 
 #[post("/products", format = "json", data = "<new_product>")]
 pub fn post_products(
     new_product: Json<NewProduct>,
     conn: db::Conn,
+    broadcaster: State<Broadcaster>,
     token: TokenData,
 ) -> Result<JsonValue, TentechError> {
     let new_product = new_product.into_inner().product;
+    new_product.check_links()?;
+    let links = new_product.links(&[]);
 
     let mut extractor = FieldValidator::validate(&new_product);
     let title = extractor.extract("title", new_product.title);
@@ -48,21 +230,32 @@ pub fn post_products(
     extractor
         .check()
         .map_err(|e| TentechError::ValidationFailed(e.errors))?;
+    let inspired_by = new_product.inspired_by.clone().unwrap_or(None);
+    let inspired_by_id = resolve_inspired_by(&conn, &inspired_by, None, &token.user.id)?;
 
-    db::products::create(
+    // Products start as drafts unless the client asks to publish right away.
+    let publish = new_product.publish.unwrap_or(false);
+    let product = db::products::create(
         &conn,
         &title,
         &body,
         &simple,
         &img,
         &new_product.duration,
+        &new_product.duration_from_devlogs.unwrap_or(false),
         &new_product.kind,
         &new_product.status,
         &new_product.tags,
+        &links,
+        inspired_by_id.as_ref(),
+        &publish,
         &token.user.id,
-    )
-    .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
-    .map(|pd| json!({ "product": pd }))
+    )?;
+    if publish {
+        product_published(&broadcaster, &product);
+        trigger_product(&conn, PRODUCT_CREATED, &product);
+    }
+    Ok(json!({ "product": product }))
 }
 
 #[patch("/products/<id>", format = "json", data = "<update_product>")]
@@ -72,15 +265,21 @@ pub fn update_products(
     token: TokenData,
     id: String,
 ) -> Result<JsonValue, TentechError> {
-    let uuid = Uuid::parse_str(&id).unwrap();
-    let product = db::products::find(&conn, &uuid)
-        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))?;
-    if !(product.user_id == token.user.id) {
+    let product = find_product(&conn, &id)?;
+    if !member_of(&conn, &product, Some(token.user.id))?.map_or(false, |m| m.can_edit()) {
         return Err(TentechError::Unauthorized(
-            "Cannot delete other's product".to_string(),
+            "Cannot update other's product".to_string(),
         ));
     }
     let update_product = update_product.into_inner();
+    update_product.check_links()?;
+    let links = if update_product.has_links() {
+        let current = db::links::find_by_product_id(&conn, &product.id)
+            .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))?;
+        Some(update_product.links(&current))
+    } else {
+        None
+    };
 
     let mut extractor = FieldValidator::validate(&update_product);
     let title = extractor.extract("title", update_product.title);
@@ -91,6 +290,13 @@ pub fn update_products(
     extractor
         .check()
         .map_err(|e| TentechError::ValidationFailed(e.errors))?;
+    // The lineage is kept unless the field is sent; `null` or "" clears it.
+    let inspired_by_id = match &update_product.inspired_by {
+        Some(inspired_by) => {
+            resolve_inspired_by(&conn, inspired_by, Some(product.id), &token.user.id)?
+        }
+        None => product.inspired_by_id,
+    };
     db::products::update(
         &conn,
         &title,
@@ -98,14 +304,21 @@ pub fn update_products(
         &simple,
         &img,
         &update_product.duration,
+        &update_product
+            .duration_from_devlogs
+            .unwrap_or(product.duration_from_devlogs),
         &update_product.kind,
         &update_product.status,
         &update_product.tags,
+        links.as_ref(),
+        inspired_by_id.as_ref(),
         &token.user.id,
-        &uuid,
+        &product.uuid,
     )
-    .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
-    .map(|pd| json!({ "product": pd }))
+    .map(|pd| {
+        trigger_product(&conn, PRODUCT_UPDATED, &pd);
+        json!({ "product": pd })
+    })
 }
 
 #[delete("/products/<id>")]
@@ -114,92 +327,356 @@ pub fn delete_products(
     token: TokenData,
     id: String,
 ) -> Result<JsonValue, TentechError> {
-    let uuid = Uuid::parse_str(&id).unwrap();
-    let product = db::products::find(&conn, &uuid)
-        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))?;
-    if !(product.user_id == token.user.id) {
+    let product = find_product(&conn, &id)?;
+    if !member_of(&conn, &product, Some(token.user.id))?.map_or(false, |m| m.is_owner()) {
         return Err(TentechError::Unauthorized(
             "Cannot delete other's product".to_string(),
         ));
     }
-    db::products::delete(&conn, &uuid)
+    db::products::delete(&conn, &product.uuid)
         .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
-        .map(|_| json!({}))
+        .map(|pd| json!({ "product": pd, "purge_at": trash::purge_at(&pd) }))
 }
 
-#[get("/products/<id>")]
-pub fn get(conn: db::Conn, id: String) -> Result<JsonValue, TentechError> {
-    let uuid = Uuid::parse_str(&id).unwrap();
-    db::products::find(&conn, &uuid)
-        .and_then(|p| {
-            let user = db::users::find(&conn, &p.user_id)?;
-            let tag_ids = db::tags::get_by_product_id(&conn, &p.id)?;
-            let reactions = db::reactions::get_by_product_id(&conn, &p.id)?;
-            Ok(json!({ "product": p, "user": user, "tag_ids": tag_ids, "reactions": reactions }))
-        })
+#[post("/products/<id>/restore")]
+pub fn restore(conn: db::Conn, token: TokenData, id: String) -> Result<JsonValue, TentechError> {
+    let not_found = || TentechError::NotFound("Product not found".to_string());
+    let uuid = Uuid::parse_str(&id).map_err(|_| not_found())?;
+    let product = db::products::find_deleted(&conn, &uuid).map_err(|e| match e {
+        Error::NotFound => not_found(),
+        e => TentechError::DatabaseFailed(format!("{}", e)),
+    })?;
+    if !member_of(&conn, &product, Some(token.user.id))?.map_or(false, |m| m.is_owner()) {
+        return Err(not_found());
+    }
+    if !trash::can_restore(&product) {
+        return Err(TentechError::InvalidTransition(
+            "Product can no longer be restored".to_string(),
+        ));
+    }
+    db::products::restore(&conn, &product.uuid)
         .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|pd| json!({ "product": pd }))
 }
 
-#[get("/users/<user_id>/products")]
-pub fn get_by_user_id(conn: db::Conn, user_id: i32) -> Result<JsonValue, TentechError> {
-    db::products::find_by_user_id(&conn, &user_id)
+#[get("/trash")]
+pub fn get_trash(conn: db::Conn, token: TokenData) -> Result<JsonValue, TentechError> {
+    db::products::find_deleted_by_user_id(&conn, &token.user.id)
         .and_then(|ps| {
-            let products_with_tags_and_reactions: Vec<_> = ps
-                .iter()
-                .map(|p| {
-                    let tag_ids = db::tags::get_by_product_id(&conn, &p.id).unwrap();
-                    let reactions = db::reactions::get_by_product_id(&conn, &p.id).unwrap();
-                    let mut json_tag = json!(p).as_object_mut().unwrap().clone();
-                    json_tag.insert("tag_ids".to_string(), json!(tag_ids).into());
-                    json_tag.insert("reactions".to_string(), json!(reactions).into());
-                    json_tag
+            let json_products: Vec<_> = listing(&conn, &ps, Some(token.user.id))?
+                .into_iter()
+                .zip(ps.iter())
+                .map(|(mut json_product, p)| {
+                    json_product.insert("purge_at".to_string(), json!(trash::purge_at(p)).into());
+                    json_product
                 })
                 .collect();
-            Ok(json!({ "products": products_with_tags_and_reactions }))
+            Ok(json!({ "products": json_products }))
         })
         .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
 }
 
-#[get("/products/recent")]
-pub fn recent(conn: db::Conn) -> Result<JsonValue, TentechError> {
-    db::products::recent(&conn)
-        .and_then(|ps| {
-            let products_with_tags_and_reactions: Vec<_> = ps
+fn change_state(
+    conn: &PgConnection,
+    broadcaster: &Broadcaster,
+    token: &TokenData,
+    id: &str,
+    from: &str,
+    to: &str,
+) -> Result<JsonValue, TentechError> {
+    let product = find_product(conn, id)?;
+    if !member_of(conn, &product, Some(token.user.id))?.map_or(false, |m| m.can_edit()) {
+        return Err(TentechError::Unauthorized(
+            "Cannot change other's product".to_string(),
+        ));
+    }
+    if product.state != from {
+        return Err(TentechError::InvalidTransition(format!(
+            "Cannot change {} product to {}",
+            product.state, to
+        )));
+    }
+    let result = if to == PUBLISHED {
+        db::products::publish(conn, &product.uuid)
+    } else {
+        db::products::set_state(conn, &product.uuid, to)
+    };
+    result
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|pd| {
+            if from == DRAFT {
+                product_published(broadcaster, &pd);
+                trigger_product(conn, PRODUCT_CREATED, &pd);
+            }
+            json!({ "product": pd })
+        })
+}
+
+#[post("/products/<id>/publish")]
+pub fn publish(
+    conn: db::Conn,
+    broadcaster: State<Broadcaster>,
+    token: TokenData,
+    id: String,
+) -> Result<JsonValue, TentechError> {
+    change_state(&conn, &broadcaster, &token, &id, DRAFT, PUBLISHED)
+}
+
+#[post("/products/<id>/archive")]
+pub fn archive(
+    conn: db::Conn,
+    broadcaster: State<Broadcaster>,
+    token: TokenData,
+    id: String,
+) -> Result<JsonValue, TentechError> {
+    change_state(&conn, &broadcaster, &token, &id, PUBLISHED, ARCHIVED)
+}
+
+#[post("/products/<id>/unarchive")]
+pub fn unarchive(
+    conn: db::Conn,
+    broadcaster: State<Broadcaster>,
+    token: TokenData,
+    id: String,
+) -> Result<JsonValue, TentechError> {
+    change_state(&conn, &broadcaster, &token, &id, ARCHIVED, PUBLISHED)
+}
+
+/// Counts the reactions of the products and those given by the viewer, if any.
+fn reaction_counts(
+    conn: &PgConnection,
+    ids: &Vec<i32>,
+    user_id: Option<i32>,
+) -> Result<(db::reactions::Counts, db::reactions::Counts), Error> {
+    let counts = db::reactions::count_by_product_ids(conn, ids)?;
+    let mine = match user_id {
+        Some(user_id) => db::reactions::count_by_user_id(conn, ids, &user_id)?,
+        None => HashMap::new(),
+    };
+    Ok((counts, mine))
+}
+
+/// Reads the 1-based `page` and its size, 20 by default and at most 100.
+pub fn paginate(page: Option<i64>, per_page: Option<i64>) -> (i64, i64) {
+    (
+        // Bounded so that the offset of the page cannot overflow.
+        page.unwrap_or(1).max(1).min(std::i64::MAX / 100),
+        per_page.unwrap_or(20).max(1).min(100),
+    )
+}
+
+/// Builds the json representation shared by every product listing, with the
+/// reactions given by `user_id` as `my_reactions`.
+pub fn listing(
+    conn: &PgConnection,
+    ps: &Vec<Product>,
+    user_id: Option<i32>,
+) -> Result<Vec<Map<String, Value>>, Error> {
+    let ids: Vec<i32> = ps.iter().map(|p| p.id).collect();
+    let comment_counts = db::comments::count_by_product_ids(conn, &ids)?;
+    let (reactions, my_reactions) = reaction_counts(conn, &ids, user_id)?;
+    let links = db::links::find_by_product_ids(conn, &ids)?;
+    ps.iter()
+        .map(|p| {
+            let user = db::users::find(conn, &p.user_id)?;
+            let tag_ids = db::tags::get_by_product_id(conn, &p.id)?;
+            let comment_count = comment_counts.get(&p.id).unwrap_or(&0);
+            let mut json_tag = json!(p).as_object_mut().unwrap().clone();
+            json_tag.insert("tag_ids".to_string(), json!(tag_ids).into());
+            json_tag.insert(
+                "reactions".to_string(),
+                json!(reactions.get(&p.id).cloned().unwrap_or_default()).into(),
+            );
+            json_tag.insert(
+                "my_reactions".to_string(),
+                json!(my_reactions.get(&p.id).cloned().unwrap_or_default()).into(),
+            );
+            json_tag.insert("user".to_string(), json!(user.to_public()).into());
+            let product_links: Vec<_> = links.iter().filter(|l| l.product_id == p.id).collect();
+            json_tag.insert("comment_count".to_string(), json!(comment_count).into());
+            json_tag.insert("links".to_string(), json!(product_links).into());
+            Ok(json_tag)
+        })
+        .collect()
+}
+
+fn detail(
+    conn: &PgConnection,
+    product: Product,
+    user_id: Option<i32>,
+) -> Result<JsonValue, TentechError> {
+    db::users::find(conn, &product.user_id)
+        .and_then(|user| {
+            let tag_ids = db::tags::get_by_product_id(conn, &product.id)?;
+            let (mut reactions, mut my_reactions) =
+                reaction_counts(conn, &vec![product.id], user_id)?;
+            let comment_count = db::comments::count_by_product_ids(conn, &vec![product.id])?
+                .get(&product.id)
+                .cloned()
+                .unwrap_or(0);
+            let links = db::links::find_by_product_id(conn, &product.id)?;
+            let media: Vec<_> = db::media::find_by_product_id(conn, &product.id)?
                 .iter()
-                .map(|p| {
-                    let user = db::users::find(&conn, &p.user_id).unwrap();
-                    let tag_ids = db::tags::get_by_product_id(&conn, &p.id).unwrap();
-                    let reactions = db::reactions::get_by_product_id(&conn, &p.id).unwrap();
-                    let mut json_tag = json!(p).as_object_mut().unwrap().clone();
-                    json_tag.insert("tag_ids".to_string(), json!(tag_ids).into());
-                    json_tag.insert("reactions".to_string(), json!(reactions).into());
-                    json_tag.insert("user".to_string(), json!(user).into());
-                    json_tag
-                })
+                .map(media_json)
                 .collect();
-            Ok(json!({ "products": products_with_tags_and_reactions }))
+            let ancestors = db::products::ancestors(conn, &product.id)?;
+            let descendants = db::products::descendants(conn, &product.id)?;
+            Ok(json!({
+                "product": product,
+                "user": user.to_public(),
+                "tag_ids": tag_ids,
+                "reactions": reactions.remove(&product.id).unwrap_or_default(),
+                "my_reactions": my_reactions.remove(&product.id).unwrap_or_default(),
+                "comment_count": comment_count,
+                "links": links,
+                "media": media,
+                "lineage": { "ancestors": ancestors, "descendants": descendants },
+            }))
         })
         .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
 }
 
-#[get("/products/popular")]
-pub fn popular(conn: db::Conn) -> Result<JsonValue, TentechError> {
-    db::products::popular(&conn)
-        .and_then(|ps| {
-            let products_with_tags_and_reactions: Vec<_> = ps
-                .iter()
-                .map(|p| {
-                    let user = db::users::find(&conn, &p.user_id).unwrap();
-                    let tag_ids = db::tags::get_by_product_id(&conn, &p.id).unwrap();
-                    let reactions = db::reactions::get_by_product_id(&conn, &p.id).unwrap();
-                    let mut json_tag = json!(p).as_object_mut().unwrap().clone();
-                    json_tag.insert("tag_ids".to_string(), json!(tag_ids).into());
-                    json_tag.insert("reactions".to_string(), json!(reactions).into());
-                    json_tag.insert("user".to_string(), json!(user).into());
-                    json_tag
+/// Counts the visit, leaving out the members' own views of the product. The
+/// product is shown either way, so a failure is only logged.
+fn record_view(conn: &PgConnection, product: &Product, user_id: Option<i32>, visit: &Visit) {
+    let recorded = member_of(conn, product, user_id).and_then(|member| {
+        if member.is_some() {
+            return Ok(());
+        }
+        db::views::record(
+            conn,
+            &product.id,
+            &visit.viewer(user_id),
+            visit.referrer.as_ref().map(String::as_str),
+        )
+        .map(|_| ())
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+    });
+    if let Err(e) = recorded {
+        println!("Could not record a view of {}: {:?}", product.uuid, e);
+    }
+}
+
+#[get("/products/<id>")]
+pub fn get(
+    conn: db::Conn,
+    token: Option<TokenData>,
+    visit: Visit,
+    id: String,
+) -> Result<JsonValue, TentechError> {
+    let product = find_product(&conn, &id)?;
+    let user_id = token.map(|t| t.user.id);
+    check_visible(&conn, &product, user_id)?;
+    record_view(&conn, &product, user_id, &visit);
+    detail(&conn, product, user_id)
+}
+
+#[derive(Responder)]
+pub enum SlugResponse {
+    Found(JsonValue),
+    Moved(Redirect),
+}
+
+#[get("/users/<username>/products/<slug>")]
+pub fn get_by_slug(
+    conn: db::Conn,
+    token: Option<TokenData>,
+    visit: Visit,
+    username: String,
+    slug: String,
+) -> Result<SlugResponse, TentechError> {
+    let user_id = token.map(|t| t.user.id);
+    let product = find_product_by_slug(&conn, &username, &slug, user_id)?;
+    // The product may have been renamed since the link was shared.
+    if product.slug != slug {
+        return Ok(SlugResponse::Moved(Redirect::moved(format!(
+            "/users/{}/products/{}",
+            username, product.slug
+        ))));
+    }
+    record_view(&conn, &product, user_id, &visit);
+    detail(&conn, product, user_id).map(SlugResponse::Found)
+}
+
+#[get("/products/<id>/derived")]
+pub fn get_derived(
+    conn: db::Conn,
+    token: Option<TokenData>,
+    id: String,
+) -> Result<JsonValue, TentechError> {
+    let product = find_product(&conn, &id)?;
+    let user_id = token.map(|t| t.user.id);
+    check_visible(&conn, &product, user_id)?;
+    db::products::find_derived(&conn, &product.id)
+        .and_then(|ps| listing(&conn, &ps, user_id))
+        .map(|ps| json!({ "products": ps }))
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+}
+
+#[get("/users/<user_id>/products?<sort>")]
+pub fn get_by_user_id(
+    conn: db::Conn,
+    token: Option<TokenData>,
+    user_id: i32,
+    sort: Option<String>,
+) -> Result<JsonValue, TentechError> {
+    let viewer_id = token.map(|t| t.user.id);
+    let is_owner = viewer_id == Some(user_id);
+    let sort = db::products::Sort::from_param(sort);
+    db::products::find_by_user_id(&conn, &user_id, is_owner, &sort)
+        .and_then(|ps| listing(&conn, &ps, viewer_id))
+        .map(|ps| json!({ "products": ps }))
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+}
+
+#[get("/products/recent?<sort>")]
+pub fn recent(
+    conn: db::Conn,
+    token: Option<TokenData>,
+    sort: Option<String>,
+) -> Result<JsonValue, TentechError> {
+    db::products::recent(&conn, &db::products::Sort::from_param(sort))
+        .and_then(|ps| listing(&conn, &ps, token.map(|t| t.user.id)))
+        .map(|ps| json!({ "products": ps }))
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+}
+
+/// Ranks the published products by their weighted reactions.
+#[get("/products/popular?<page>&<per_page>")]
+pub fn popular(
+    conn: db::Conn,
+    token: Option<TokenData>,
+    page: Option<i64>,
+    per_page: Option<i64>,
+) -> Result<JsonValue, TentechError> {
+    let (page, per_page) = paginate(page, per_page);
+    db::products::popular(&conn, page - 1, per_page)
+        .and_then(|ranked| {
+            let (ps, scores): (Vec<Product>, Vec<i64>) = ranked.into_iter().unzip();
+            let products: Vec<_> = listing(&conn, &ps, token.map(|t| t.user.id))?
+                .into_iter()
+                .zip(scores)
+                .map(|(mut p, score)| {
+                    p.insert("score".to_string(), json!(score).into());
+                    p
                 })
                 .collect();
-            Ok(json!({ "products": products_with_tags_and_reactions }))
+            Ok(json!({ "products": products, "page": page, "per_page": per_page }))
         })
         .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
 }
+
+/// Ranks products by the reactions they got within `window` (`24h`, `7d`,
+/// `30d` or `all`), as of the last refresh of the scores.
+#[get("/products/trending?<window>")]
+pub fn trending(
+    conn: db::Conn,
+    token: Option<TokenData>,
+    window: Option<String>,
+) -> Result<JsonValue, TentechError> {
+    let window = db::trending::Window::from_param(window);
+    db::trending::find(&conn, &window, 50)
+        .and_then(|ps| listing(&conn, &ps, token.map(|t| t.user.id)))
+        .map(|ps| json!({ "window": window.name(), "products": ps }))
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+}
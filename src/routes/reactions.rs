@@ -1,14 +1,105 @@
 use crate::db;
 use crate::error::TentechError;
-use crate::models::user::TokenData;
+use crate::events::Broadcaster;
+use crate::models::notification::REACTION;
+use crate::models::product::Product;
+use crate::models::reaction::Reaction;
+use crate::models::user::{TokenData, User};
+use crate::models::webhook::REACTION_ADDED;
+use crate::routes::events::reactions_changed;
+use crate::routes::notifications::notify;
+use crate::routes::products::{
+    check_visible, find_product, find_product_by_slug, member_of, paginate,
+};
+use crate::routes::webhooks::trigger_reaction;
+use crate::validation::field_error;
+use diesel::pg::PgConnection;
+use diesel::result::Error;
+use rocket::State;
 use rocket_contrib::json::{Json, JsonValue};
 use serde::Deserialize;
+use serde_json::Value;
+use std::env;
 
 #[derive(Deserialize)]
 pub struct NewReaction {
     pub kind: String,
 }
 
+/// Only the kinds of the catalogue can be used to react.
+fn check_kind(conn: &PgConnection, kind: &str) -> Result<(), TentechError> {
+    match db::reactions::find_kind(conn, kind) {
+        Ok(_) => Ok(()),
+        Err(Error::NotFound) => Err(field_error("kind", "unknown_kind", "Unknown reaction kind")),
+        Err(e) => Err(TentechError::DatabaseFailed(format!("{}", e))),
+    }
+}
+
+#[get("/reactions/kinds")]
+pub fn get_kinds(conn: db::Conn) -> Result<JsonValue, TentechError> {
+    db::reactions::kinds(&conn)
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|ks| json!({ "kinds": ks }))
+}
+
+/// Members cannot react to their own products when `PREVENT_SELF_REACTIONS` is set.
+fn check_not_own(
+    conn: &PgConnection,
+    product: &Product,
+    user_id: &i32,
+) -> Result<(), TentechError> {
+    let prevent = env::var("PREVENT_SELF_REACTIONS").map_or(false, |v| v == "true" || v == "1");
+    if prevent && member_of(conn, product, Some(*user_id))?.is_some() {
+        return Err(TentechError::CannotReactToOwn);
+    }
+    Ok(())
+}
+
+fn add_reaction(
+    conn: &PgConnection,
+    broadcaster: &Broadcaster,
+    token: &TokenData,
+    product: Product,
+    new_reaction: NewReaction,
+) -> Result<JsonValue, TentechError> {
+    check_visible(conn, &product, Some(token.user.id))?;
+    check_kind(conn, &new_reaction.kind)?;
+    check_not_own(conn, &product, &token.user.id)?;
+    db::reactions::add_react(conn, &new_reaction, &product.id, &token.user.id)?;
+    notify(
+        conn,
+        broadcaster,
+        &product.user_id,
+        REACTION,
+        Some(&product.id),
+        &new_reaction.kind,
+        &token.user.id,
+    );
+    trigger_reaction(
+        conn,
+        REACTION_ADDED,
+        &product,
+        &new_reaction.kind,
+        &token.user,
+    );
+    reactions_changed(conn, broadcaster, &product);
+    Ok(json!({}))
+}
+
+fn sub_reaction(
+    conn: &PgConnection,
+    broadcaster: &Broadcaster,
+    token: &TokenData,
+    product: Product,
+    new_reaction: NewReaction,
+) -> Result<JsonValue, TentechError> {
+    check_visible(conn, &product, Some(token.user.id))?;
+    check_kind(conn, &new_reaction.kind)?;
+    db::reactions::sub_react(conn, &new_reaction, &product.id, &token.user.id)?;
+    reactions_changed(conn, broadcaster, &product);
+    Ok(json!({}))
+}
+
 #[post(
     "/products/<id>/reaction/add",
     format = "json",
@@ -17,13 +108,18 @@ pub struct NewReaction {
 pub fn add_react(
     new_reaction: Json<NewReaction>,
     conn: db::Conn,
+    broadcaster: State<Broadcaster>,
     token: TokenData,
-    id: i32,
+    id: String,
 ) -> Result<JsonValue, TentechError> {
-    let new_reaction = new_reaction.into_inner();
-    db::reactions::add_react(&conn, &new_reaction, &id, &token.user.id)
-        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
-        .map(|_| json!({}))
+    let product = find_product(&conn, &id)?;
+    add_reaction(
+        &conn,
+        &broadcaster,
+        &token,
+        product,
+        new_reaction.into_inner(),
+    )
 }
 
 #[post(
@@ -34,18 +130,119 @@ pub fn add_react(
 pub fn sub_react(
     new_reaction: Json<NewReaction>,
     conn: db::Conn,
+    broadcaster: State<Broadcaster>,
     token: TokenData,
+    id: String,
+) -> Result<JsonValue, TentechError> {
+    let product = find_product(&conn, &id)?;
+    sub_reaction(
+        &conn,
+        &broadcaster,
+        &token,
+        product,
+        new_reaction.into_inner(),
+    )
+}
+
+#[post(
+    "/users/<username>/products/<slug>/reaction/add",
+    format = "json",
+    data = "<new_reaction>"
+)]
+pub fn add_react_by_slug(
+    new_reaction: Json<NewReaction>,
+    conn: db::Conn,
+    broadcaster: State<Broadcaster>,
+    token: TokenData,
+    username: String,
+    slug: String,
+) -> Result<JsonValue, TentechError> {
+    let product = find_product_by_slug(&conn, &username, &slug, Some(token.user.id))?;
+    add_reaction(
+        &conn,
+        &broadcaster,
+        &token,
+        product,
+        new_reaction.into_inner(),
+    )
+}
+
+#[post(
+    "/users/<username>/products/<slug>/reaction/sub",
+    format = "json",
+    data = "<new_reaction>"
+)]
+pub fn sub_react_by_slug(
+    new_reaction: Json<NewReaction>,
+    conn: db::Conn,
+    broadcaster: State<Broadcaster>,
+    token: TokenData,
+    username: String,
+    slug: String,
+) -> Result<JsonValue, TentechError> {
+    let product = find_product_by_slug(&conn, &username, &slug, Some(token.user.id))?;
+    sub_reaction(
+        &conn,
+        &broadcaster,
+        &token,
+        product,
+        new_reaction.into_inner(),
+    )
+}
+
+fn feed_json(resources: Vec<(Reaction, Product, User)>) -> Vec<Value> {
+    resources
+        .iter()
+        .map(|(r, p, u)| {
+            json!({
+                "reaction": r,
+                "product": { "id": p.uuid, "title": p.title, "slug": p.slug, "img": p.img },
+                "user": u.to_public(),
+            })
+            .into()
+        })
+        .collect()
+}
+
+/// Reactions other users left on the products of the user, latest first.
+#[get("/users/<id>/reactions/received?<page>&<per_page>")]
+pub fn get_received(
+    conn: db::Conn,
     id: i32,
+    page: Option<i64>,
+    per_page: Option<i64>,
 ) -> Result<JsonValue, TentechError> {
-    let new_reaction = new_reaction.into_inner();
-    db::reactions::sub_react(&conn, &new_reaction, &id, &token.user.id)
+    let (page, per_page) = paginate(page, per_page);
+    db::reactions::received_by_user_id(&conn, &id, page - 1, per_page)
         .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
-        .map(|_| json!({}))
+        .map(|rs| json!({ "reactions": feed_json(rs), "page": page, "per_page": per_page }))
 }
 
+/// The former feed of reactions received, kept for older clients: the latest
+/// 20 as `[product, reaction, user]` triples.
 #[get("/users/<id>/reactions")]
 pub fn get_by_user_id(conn: db::Conn, id: i32) -> Result<JsonValue, TentechError> {
-    db::reactions::get_by_user_id(&conn, &id)
+    db::reactions::received_by_user_id(&conn, &id, 0, 20)
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|rs| {
+            let triples: Vec<_> = rs
+                .iter()
+                .map(|(r, p, u)| json!([p, r, u.to_public()]))
+                .collect();
+            json!(triples)
+        })
+}
+
+/// Reactions the user left on products, latest first.
+#[get("/users/<id>/reactions/given?<page>&<per_page>")]
+pub fn get_given(
+    conn: db::Conn,
+    id: i32,
+    page: Option<i64>,
+    per_page: Option<i64>,
+) -> Result<JsonValue, TentechError> {
+    let (page, per_page) = paginate(page, per_page);
+    db::reactions::given_by_user_id(&conn, &id, page - 1, per_page)
         .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
-        .map(|r| json!(r))
+        .map(|rs| json!({ "reactions": feed_json(rs), "page": page, "per_page": per_page }))
 }
@@ -0,0 +1,82 @@
+use crate::db;
+use crate::error::TentechError;
+use crate::models::revision::Revision;
+use crate::models::user::TokenData;
+use crate::routes::products::{check_visible, find_product, member_of};
+use diesel::pg::PgConnection;
+use diesel::result::Error;
+use rocket_contrib::json::JsonValue;
+
+/// Looks up the revision of the product, answering 404 for unknown numbers.
+fn find_revision(
+    conn: &PgConnection,
+    product_id: &i32,
+    number: &i32,
+) -> Result<Revision, TentechError> {
+    db::revisions::find(conn, product_id, number).map_err(|e| match e {
+        Error::NotFound => TentechError::NotFound("Revision not found".to_string()),
+        e => TentechError::DatabaseFailed(format!("{}", e)),
+    })
+}
+
+#[get("/products/<id>/revisions")]
+pub fn get_by_product_id(
+    conn: db::Conn,
+    token: Option<TokenData>,
+    id: String,
+) -> Result<JsonValue, TentechError> {
+    let product = find_product(&conn, &id)?;
+    check_visible(&conn, &product, token.map(|t| t.user.id))?;
+    db::revisions::find_by_product_id(&conn, &product.id)
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|r| json!({ "revisions": r }))
+}
+
+#[get("/products/<id>/revisions/diff?<from>&<to>")]
+pub fn diff(
+    conn: db::Conn,
+    token: Option<TokenData>,
+    id: String,
+    from: i32,
+    to: i32,
+) -> Result<JsonValue, TentechError> {
+    let product = find_product(&conn, &id)?;
+    check_visible(&conn, &product, token.map(|t| t.user.id))?;
+    let from = find_revision(&conn, &product.id, &from)?;
+    let to = find_revision(&conn, &product.id, &to)?;
+    Ok(json!({ "from": from.number, "to": to.number, "changes": from.diff(&to) }))
+}
+
+#[post("/products/<id>/revisions/<number>/restore")]
+pub fn restore(
+    conn: db::Conn,
+    token: TokenData,
+    id: String,
+    number: i32,
+) -> Result<JsonValue, TentechError> {
+    let product = find_product(&conn, &id)?;
+    if !member_of(&conn, &product, Some(token.user.id))?.map_or(false, |m| m.can_edit()) {
+        return Err(TentechError::Unauthorized(
+            "Cannot restore other's product".to_string(),
+        ));
+    }
+    let revision = find_revision(&conn, &product.id, &number)?;
+    // Restoring records a new revision instead of rewinding the history.
+    db::products::update(
+        &conn,
+        &revision.title,
+        &revision.body,
+        &revision.simple,
+        &revision.img,
+        &revision.duration,
+        &product.duration_from_devlogs,
+        &revision.kind,
+        &revision.status,
+        &revision.tag_ids,
+        None,
+        product.inspired_by_id.as_ref(),
+        &token.user.id,
+        &product.uuid,
+    )
+    .map(|pd| json!({ "product": pd }))
+}
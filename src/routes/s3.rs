@@ -1,5 +1,6 @@
 use crate::error::TentechError;
 use crate::models::user::TokenData;
+use crate::s3;
 use base64;
 use rocket::State;
 use rocket_contrib::json::{Json, JsonValue};
@@ -21,17 +22,21 @@ pub struct NewAssetData {
 
 #[derive(Serialize)]
 pub struct CreatedAsset {
+    key: String,
     url: String,
 }
 #[post("/upload", format = "json", data = "<new_asset>")]
 pub fn upload(
     new_asset: Json<NewAsset>,
     client: State<S3Client>,
+    token: TokenData,
 ) -> Result<JsonValue, TentechError> {
     let new_asset = new_asset.into_inner().asset;
+    // The given key only names the file; where it is stored is up to us.
+    let key = s3::new_key(&token.user.id, &new_asset.key);
     let mut request = PutObjectRequest::default();
     request.bucket = String::from("tentech");
-    request.key = new_asset.key.to_string();
+    request.key = key.clone();
     request.acl = Some(String::from("public-read"));
     request.content_type = Some(new_asset.content_type.to_string());
     let body =
@@ -44,8 +49,9 @@ pub fn upload(
     client
         .put_object(request)
         .sync()
-        .map(|a| json!({ "asset": CreatedAsset { url: format!("https://tentech.s3-ap-northeast-1.amazonaws.com/{}",new_asset.key)}}))
+        .map(|a| json!({ "asset": CreatedAsset { url: s3::object_url(&key), key }}))
         .map_err(|e| {
-            println!("{}",e);
-            TentechError::CannotPutS3Object})
+            println!("{}", e);
+            TentechError::CannotPutS3Object
+        })
 }
@@ -2,6 +2,7 @@ use crate::db;
 use crate::error::TentechError;
 use crate::models::product::Product;
 use crate::models::suggestion::Suggestion;
+use crate::routes::products::listing;
 use rocket_contrib::json::{Json, JsonValue};
 use serde::Deserialize;
 use serde_json;
@@ -63,19 +64,8 @@ pub fn suggestion(conn: db::Conn, data: Json<SuggestionForm>) -> Result<JsonValu
             .map(|i| i.as_str().unwrap().to_string())
             .collect();
         suggestion.products = db::products::find_by_tag_name(&conn, &data.lang.to_string())
-            .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))?
-            .iter()
-            .map(|p| {
-                let user = db::users::find(&conn, &p.user_id).unwrap();
-                let tag_ids = db::tags::get_by_product_id(&conn, &p.id).unwrap();
-                let reactions = db::reactions::get_by_product_id(&conn, &p.id).unwrap();
-                let mut json_tag = json!(p).as_object_mut().unwrap().clone();
-                json_tag.insert("tag_ids".to_string(), json!(tag_ids).into());
-                json_tag.insert("reactions".to_string(), json!(reactions).into());
-                json_tag.insert("user".to_string(), json!(user).into());
-                json_tag
-            })
-            .collect();
+            .and_then(|ps| listing(&conn, &ps, None))
+            .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))?;
     } else {
         let lang = "Python".to_string();
         suggestion.title = format!(
@@ -109,19 +99,8 @@ pub fn suggestion(conn: db::Conn, data: Json<SuggestionForm>) -> Result<JsonValu
             .map(|i| i.as_str().unwrap().to_string())
             .collect();
         suggestion.products = db::products::find_by_tag_name(&conn, &lang.to_string())
-            .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))?
-            .iter()
-            .map(|p| {
-                let user = db::users::find(&conn, &p.user_id).unwrap();
-                let tag_ids = db::tags::get_by_product_id(&conn, &p.id).unwrap();
-                let reactions = db::reactions::get_by_product_id(&conn, &p.id).unwrap();
-                let mut json_tag = json!(p).as_object_mut().unwrap().clone();
-                json_tag.insert("tag_ids".to_string(), json!(tag_ids).into());
-                json_tag.insert("reactions".to_string(), json!(reactions).into());
-                json_tag.insert("user".to_string(), json!(user).into());
-                json_tag
-            })
-            .collect();
+            .and_then(|ps| listing(&conn, &ps, None))
+            .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))?;
     }
     Ok(json!({ "suggestion": suggestion }))
 }
@@ -1,6 +1,8 @@
 use crate::db;
 use crate::error::TentechError;
 use crate::models::user::TokenData;
+use crate::models::webhook::USER_ACTIVATED;
+use crate::routes::webhooks::trigger_user;
 use crate::schema::users;
 use crate::validation::FieldValidator;
 use lazy_static::lazy_static;
@@ -107,12 +109,13 @@ pub fn activate(token: String, conn: db::Conn) -> Result<JsonValue, TentechError
     if target.activated {
         return Err(TentechError::AlreadyActivated);
     }
-    db::users::activate(&conn, &token_data.user)
+    let activated = db::users::activate(&conn, &token_data.user)
         .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))?;
 
     if !token_data.check_expired() {
         return Err(TentechError::TokenExpired);
     }
+    trigger_user(&conn, USER_ACTIVATED, &activated);
 
     Ok(json!(token_data))
 }
@@ -0,0 +1,35 @@
+use crate::db;
+use crate::error::TentechError;
+use crate::models::user::TokenData;
+use crate::routes::products::{find_product, member_of};
+use rocket_contrib::json::JsonValue;
+
+const DEFAULT_DAYS: i32 = 30;
+const MAX_DAYS: i32 = 365;
+
+#[get("/products/<id>/analytics?<days>")]
+pub fn analytics(
+    conn: db::Conn,
+    token: TokenData,
+    id: String,
+    days: Option<i32>,
+) -> Result<JsonValue, TentechError> {
+    let product = find_product(&conn, &id)?;
+    if !member_of(&conn, &product, Some(token.user.id))?.map_or(false, |m| m.is_owner()) {
+        return Err(TentechError::Unauthorized(
+            "Cannot see analytics of other's product".to_string(),
+        ));
+    }
+    let days = days.unwrap_or(DEFAULT_DAYS).max(1).min(MAX_DAYS);
+    let views = db::views::daily(&conn, &product.id, &days)?;
+    let reactions = db::views::reactions_daily(&conn, &product.id, &days)?;
+    let referrers = db::views::referrers(&conn, &product.id, &days)?;
+    let total: i64 = views.iter().map(|v| v.views).sum();
+    Ok(json!({
+        "days": days,
+        "total_views": total,
+        "views": views,
+        "reactions": reactions,
+        "referrers": referrers,
+    }))
+}
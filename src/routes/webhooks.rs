@@ -0,0 +1,288 @@
+use crate::db;
+use crate::error::TentechError;
+use crate::models::product::Product;
+use crate::models::user::{TokenData, User};
+use crate::models::webhook::{Webhook, EVENTS, USER_ACTIVATED};
+use crate::net;
+use crate::routes::products::paginate;
+use crate::validation::{field_error, FieldValidator};
+use crate::webhooks;
+use diesel::pg::PgConnection;
+use diesel::result::Error;
+use reqwest::Url;
+use rocket_contrib::json::{Json, JsonValue};
+use serde::Deserialize;
+use serde_json::Value;
+use std::env;
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Deserialize)]
+pub struct NewWebhook {
+    webhook: NewWebhookData,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct NewWebhookData {
+    #[validate(url, length(max = "2000"))]
+    url: Option<String>,
+    events: Option<Vec<String>>,
+    tag_ids: Option<Vec<i32>>,
+}
+
+/// Admins are the users named in `ADMIN_USERNAMES`, separated by commas.
+fn is_admin(user: &User) -> bool {
+    env::var("ADMIN_USERNAMES").map_or(false, |names| {
+        names.split(',').any(|name| name.trim() == user.username)
+    })
+}
+
+/// Looks up the webhook of the user addressed by `id`, answering 404 for malformed or unknown ids.
+fn find_webhook(conn: &PgConnection, token: &TokenData, id: &str) -> Result<Webhook, TentechError> {
+    let not_found = || TentechError::NotFound("Webhook not found".to_string());
+    let uuid = Uuid::parse_str(id).map_err(|_| not_found())?;
+    db::webhooks::find(conn, &token.user.id, &uuid).map_err(|e| match e {
+        Error::NotFound => not_found(),
+        e => TentechError::DatabaseFailed(format!("{}", e)),
+    })
+}
+
+/// Queues the event for the webhooks wanting it. The action has already
+/// succeeded, so a failure is only logged instead of failing the request.
+fn trigger(conn: &PgConnection, event: &str, data: Value, tag_ids: &[i32]) {
+    if let Err(e) = webhooks::dispatch(conn, event, data, tag_ids) {
+        println!("Could not dispatch {}: {}", event, e);
+    }
+}
+
+/// Triggers a product event, as long as the product can be seen by anyone.
+pub fn trigger_product(conn: &PgConnection, event: &str, product: &Product) {
+    if !product.is_published() {
+        return;
+    }
+    let payload = db::tags::get_by_product_id(conn, &product.id).and_then(|tag_ids| {
+        let user = db::users::find(conn, &product.user_id)?;
+        let data = json!({ "product": product, "tag_ids": tag_ids, "user": user.to_public() });
+        Ok((data, tag_ids))
+    });
+    match payload {
+        Ok((data, tag_ids)) => trigger(conn, event, data.into(), &tag_ids),
+        Err(e) => println!("Could not dispatch {}: {}", event, e),
+    }
+}
+
+/// Triggers a reaction event on a product which can be seen by anyone.
+pub fn trigger_reaction(
+    conn: &PgConnection,
+    event: &str,
+    product: &Product,
+    kind: &str,
+    user: &User,
+) {
+    if !product.is_published() {
+        return;
+    }
+    match db::tags::get_by_product_id(conn, &product.id) {
+        Ok(tag_ids) => trigger(
+            conn,
+            event,
+            json!({ "product": product, "kind": kind, "user": user.to_public() }).into(),
+            &tag_ids,
+        ),
+        Err(e) => println!("Could not dispatch {}: {}", event, e),
+    }
+}
+
+pub fn trigger_user(conn: &PgConnection, event: &str, user: &User) {
+    trigger(conn, event, json!({ "user": user.to_public() }).into(), &[]);
+}
+
+#[get("/webhooks")]
+pub fn get_webhooks(conn: db::Conn, token: TokenData) -> Result<JsonValue, TentechError> {
+    db::webhooks::find_by_user_id(&conn, &token.user.id)
+        .map(|ws| json!({ "webhooks": ws }))
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+}
+
+/// Registers a webhook. The secret signing its payloads is only shown here.
+#[post("/webhooks", format = "json", data = "<new_webhook>")]
+pub fn post_webhooks(
+    new_webhook: Json<NewWebhook>,
+    conn: db::Conn,
+    token: TokenData,
+) -> Result<JsonValue, TentechError> {
+    let new_webhook = new_webhook.into_inner().webhook;
+
+    let mut extractor = FieldValidator::validate(&new_webhook);
+    let url = extractor.extract("url", new_webhook.url);
+    let events = extractor.extract("events", new_webhook.events);
+
+    extractor
+        .check()
+        .map_err(|e| TentechError::ValidationFailed(e.errors))?;
+
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err(field_error(
+            "url",
+            "invalid_scheme",
+            "Url must be http or https",
+        ));
+    }
+    // Checked again before every delivery, as the host may change its addresses.
+    if !webhooks::allow_private()
+        && Url::parse(&url)
+            .map_err(|e| format!("{}", e))
+            .and_then(|u| net::check_public_url(&u))
+            .is_err()
+    {
+        return Err(field_error(
+            "url",
+            "private_address",
+            "Url must point to a public address",
+        ));
+    }
+    if events.is_empty() || events.iter().any(|e| !EVENTS.contains(&e.as_str())) {
+        return Err(field_error(
+            "events",
+            "unknown_event",
+            "Events must be some of product.created, product.updated, reaction.added and user.activated",
+        ));
+    }
+    if events.iter().any(|e| e == USER_ACTIVATED) && !is_admin(&token.user) {
+        return Err(TentechError::Unauthorized(
+            "Only admins can receive user events".to_string(),
+        ));
+    }
+    let tag_ids = db::tags::validate_ids(&conn, &new_webhook.tag_ids.unwrap_or_default())?;
+
+    let secret = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    db::webhooks::create(&conn, &token.user.id, &url, &secret, &events, &tag_ids)
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|w| {
+            let mut json_webhook = json!(w).as_object_mut().unwrap().clone();
+            json_webhook.insert("secret".to_string(), json!(w.secret).into());
+            json!({ "webhook": json_webhook })
+        })
+}
+
+#[delete("/webhooks/<id>")]
+pub fn delete_webhooks(
+    conn: db::Conn,
+    token: TokenData,
+    id: String,
+) -> Result<JsonValue, TentechError> {
+    let webhook = find_webhook(&conn, &token, &id)?;
+    db::webhooks::delete(&conn, &webhook.id)
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+        .map(|_| json!({}))
+}
+
+/// The delivery log of the webhook, the latest first.
+#[get("/webhooks/<id>/deliveries?<page>&<per_page>")]
+pub fn get_deliveries(
+    conn: db::Conn,
+    token: TokenData,
+    id: String,
+    page: Option<i64>,
+    per_page: Option<i64>,
+) -> Result<JsonValue, TentechError> {
+    let webhook = find_webhook(&conn, &token, &id)?;
+    let (page, per_page) = paginate(page, per_page);
+    db::webhooks::find_deliveries(&conn, &webhook.id, page - 1, per_page)
+        .map(|ds| json!({ "deliveries": ds, "page": page, "per_page": per_page }))
+        .map_err(|e| TentechError::DatabaseFailed(format!("{}", e)))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::db;
+    use crate::net::stub_server;
+    use crate::rocket;
+    use crate::test_establish_connection;
+    use crate::webhooks;
+    use reqwest::Client as HttpClient;
+    use rocket::http::{ContentType, Header, Status};
+    use rocket::local::Client;
+    use serde_json::Value;
+    use std::env;
+    use uuid::Uuid;
+
+    #[test]
+    fn failed_delivery_is_logged_and_retried_later() {
+        env::set_var("WEBHOOK_ALLOW_PRIVATE", "1");
+        let conn = test_establish_connection();
+        let name = format!("hook{}", &Uuid::new_v4().simple().to_string()[..10]);
+        let user = db::users::create(
+            &conn,
+            &name,
+            &name,
+            &format!("{}@test.com", name),
+            "passpassword",
+        )
+        .unwrap();
+        let api_key = || Header::new("x-api-key", user.generate_token());
+        let client = Client::new(rocket()).expect("valid rocket instance");
+        let (url, received) = stub_server("500 Internal Server Error", 1);
+
+        let new_webhook = json!({
+            "webhook": { "url": format!("{}/hook", url), "events": ["product.created"] }
+        });
+        let mut response = client
+            .post("/webhooks")
+            .header(ContentType::JSON)
+            .header(api_key())
+            .body(new_webhook.to_string())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let webhook: Value = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+        let webhook_id = webhook["webhook"]["uuid"].as_str().unwrap().to_string();
+
+        let new_product = json!({
+            "product": {
+                "title": "Hooked",
+                "body": "body",
+                "simple": "simple",
+                "img": "https://example.com/hooked.png",
+                "duration": 1,
+                "kind": "web",
+                "status": "developing",
+                "tags": [],
+            }
+        });
+        let mut response = client
+            .post("/products")
+            .header(ContentType::JSON)
+            .header(api_key())
+            .body(new_product.to_string())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let product: Value = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+        let response = client
+            .post(format!(
+                "/products/{}/publish",
+                product["product"]["uuid"].as_str().unwrap()
+            ))
+            .header(api_key())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+
+        assert!(webhooks::deliver_due(&conn, &HttpClient::new(), 100).unwrap() >= 1);
+        let request = received.recv().unwrap().to_lowercase();
+        assert!(request.starts_with("post /hook "));
+        assert!(request.contains("x-tentech-event: product.created"));
+
+        let mut response = client
+            .get(format!("/webhooks/{}/deliveries", webhook_id))
+            .header(api_key())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let log: Value = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+        let deliveries = log["deliveries"].as_array().unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0]["event"], "product.created");
+        assert_eq!(deliveries[0]["status"], 500);
+        assert_eq!(deliveries[0]["attempts"], 1);
+        assert!(deliveries[0]["delivered_at"].is_null());
+        assert!(!deliveries[0]["next_attempt_at"].is_null());
+    }
+}
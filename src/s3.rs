@@ -1,9 +1,57 @@
+use crate::error::TentechError;
+use crate::models::media::Media;
 use futures::{stream::Stream, Future};
 use rusoto_core::{DefaultCredentialsProvider, Region};
-use rusoto_s3::S3Client;
+use rusoto_s3::{DeleteObjectRequest, S3Client, S3};
+use uuid::Uuid;
 
 pub fn initial_s3_client() -> S3Client {
     let mut provider = DefaultCredentialsProvider::new().unwrap();
 
     S3Client::new(Region::ApNortheast1)
 }
+
+/// Stores an upload of the user under their own prefix, so that nobody can
+/// register or remove the objects of others.
+pub fn new_key(user_id: &i32, file_name: &str) -> String {
+    let name: String = file_name
+        .rsplit('/')
+        .next()
+        .unwrap_or("")
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '.' || *c == '-' || *c == '_')
+        .collect();
+    format!("users/{}/{}-{}", user_id, Uuid::new_v4().simple(), name)
+}
+
+/// Whether the object was uploaded by the user through `new_key`.
+pub fn is_owned_by(key: &str, user_id: &i32) -> bool {
+    let prefix = format!("users/{}/", user_id);
+    key.starts_with(&prefix) && !key[prefix.len()..].contains('/')
+}
+
+/// Whether removing the media may remove its stored object as well, which
+/// is only the case for objects its uploader stored under their own prefix.
+pub fn can_delete(media: &Media) -> bool {
+    media
+        .user_id
+        .map_or(false, |u| is_owned_by(&media.storage_key, &u))
+}
+
+pub fn object_url(key: &str) -> String {
+    format!("https://tentech.s3-ap-northeast-1.amazonaws.com/{}", key)
+}
+
+pub fn delete_object(client: &S3Client, key: &str) -> Result<(), TentechError> {
+    let mut request = DeleteObjectRequest::default();
+    request.bucket = String::from("tentech");
+    request.key = key.to_string();
+    client
+        .delete_object(request)
+        .sync()
+        .map(|_| ())
+        .map_err(|e| {
+            println!("{}", e);
+            TentechError::CannotDeleteS3Object
+        })
+}
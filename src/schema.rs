@@ -1,3 +1,159 @@
+table! {
+    bookmarks (id) {
+        id -> Int4,
+        user_id -> Int4,
+        product_id -> Int4,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    collection_items (id) {
+        id -> Int4,
+        collection_id -> Int4,
+        product_id -> Int4,
+        position -> Int4,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    collections (id) {
+        id -> Int4,
+        uuid -> Uuid,
+        user_id -> Int4,
+        name -> Varchar,
+        description -> Text,
+        public -> Bool,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    comments (id) {
+        id -> Int4,
+        product_id -> Int4,
+        user_id -> Int4,
+        parent_id -> Nullable<Int4>,
+        body -> Text,
+        deleted_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        body_html -> Text,
+    }
+}
+
+table! {
+    devlogs (id) {
+        id -> Int4,
+        uuid -> Uuid,
+        product_id -> Int4,
+        user_id -> Int4,
+        body -> Text,
+        hours -> Nullable<Int4>,
+        img -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        body_html -> Text,
+    }
+}
+
+table! {
+    notifications (id) {
+        id -> Int4,
+        uuid -> Uuid,
+        user_id -> Int4,
+        kind -> Varchar,
+        product_id -> Nullable<Int4>,
+        subject -> Varchar,
+        actor_ids -> Array<Int4>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        read_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    product_links (id) {
+        id -> Int4,
+        product_id -> Int4,
+        kind -> Varchar,
+        url -> Varchar,
+        status -> Nullable<Int4>,
+        error -> Nullable<Varchar>,
+        checked_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    product_media (id) {
+        id -> Int4,
+        uuid -> Uuid,
+        product_id -> Int4,
+        kind -> Varchar,
+        storage_key -> Varchar,
+        caption -> Varchar,
+        alt -> Varchar,
+        width -> Int4,
+        height -> Int4,
+        position -> Int4,
+        created_at -> Timestamp,
+        user_id -> Nullable<Int4>,
+    }
+}
+
+table! {
+    product_members (id) {
+        id -> Int4,
+        product_id -> Int4,
+        user_id -> Int4,
+        role -> Varchar,
+        invited_by -> Nullable<Int4>,
+        accepted_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    product_revisions (id) {
+        id -> Int4,
+        product_id -> Int4,
+        number -> Int4,
+        user_id -> Int4,
+        title -> Varchar,
+        body -> Text,
+        simple -> Varchar,
+        img -> Varchar,
+        kind -> Varchar,
+        status -> Varchar,
+        duration -> Int4,
+        tag_ids -> Array<Int4>,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    product_slugs (id) {
+        id -> Int4,
+        product_id -> Int4,
+        user_id -> Int4,
+        slug -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    product_views (id) {
+        id -> Int4,
+        product_id -> Int4,
+        viewer -> Varchar,
+        referrer -> Nullable<Varchar>,
+        day -> Date,
+        created_at -> Timestamp,
+    }
+}
+
 table! {
     products (id) {
         id -> Int4,
@@ -10,6 +166,15 @@ table! {
         duration -> Int4,
         user_id -> Int4,
         simple -> Varchar,
+        state -> Varchar,
+        published_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        slug -> Varchar,
+        duration_from_devlogs -> Bool,
+        body_html -> Text,
+        deleted_at -> Nullable<Timestamp>,
+        inspired_by_id -> Nullable<Int4>,
     }
 }
 
@@ -21,6 +186,25 @@ table! {
     }
 }
 
+table! {
+    reaction_counts (product_id, kind) {
+        product_id -> Int4,
+        kind -> Varchar,
+        count -> Int8,
+    }
+}
+
+table! {
+    reaction_kinds (name) {
+        name -> Varchar,
+        emoji -> Varchar,
+        label -> Varchar,
+        position -> Int4,
+        max_per_user -> Int4,
+        weight -> Int4,
+    }
+}
+
 table! {
     reactions (id) {
         id -> Int4,
@@ -40,6 +224,24 @@ table! {
     }
 }
 
+table! {
+    trending_scores (period, product_id) {
+        product_id -> Int4,
+        period -> Varchar,
+        score -> Int8,
+        refreshed_at -> Timestamp,
+    }
+}
+
+table! {
+    user_reaction_counts (user_id, product_id, kind) {
+        user_id -> Int4,
+        product_id -> Int4,
+        kind -> Varchar,
+        count -> Int4,
+    }
+}
+
 table! {
     users (id) {
         id -> Int4,
@@ -54,16 +256,92 @@ table! {
     }
 }
 
+table! {
+    webhook_deliveries (id) {
+        id -> Int4,
+        uuid -> Uuid,
+        webhook_id -> Int4,
+        event -> Varchar,
+        payload -> Text,
+        attempts -> Int4,
+        status -> Nullable<Int4>,
+        error -> Nullable<Varchar>,
+        next_attempt_at -> Nullable<Timestamp>,
+        delivered_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    webhooks (id) {
+        id -> Int4,
+        uuid -> Uuid,
+        user_id -> Int4,
+        url -> Varchar,
+        secret -> Varchar,
+        events -> Array<Text>,
+        tag_ids -> Array<Int4>,
+        created_at -> Timestamp,
+    }
+}
+
+joinable!(bookmarks -> products (product_id));
+joinable!(bookmarks -> users (user_id));
+joinable!(collection_items -> collections (collection_id));
+joinable!(collection_items -> products (product_id));
+joinable!(collections -> users (user_id));
+joinable!(comments -> products (product_id));
+joinable!(comments -> users (user_id));
+joinable!(devlogs -> products (product_id));
+joinable!(devlogs -> users (user_id));
+joinable!(notifications -> products (product_id));
+joinable!(notifications -> users (user_id));
+joinable!(product_links -> products (product_id));
+joinable!(product_media -> products (product_id));
+joinable!(product_media -> users (user_id));
+joinable!(product_members -> products (product_id));
+joinable!(product_revisions -> products (product_id));
+joinable!(product_revisions -> users (user_id));
+joinable!(product_slugs -> products (product_id));
+joinable!(product_slugs -> users (user_id));
+joinable!(product_views -> products (product_id));
 joinable!(products -> users (user_id));
 joinable!(products_tags -> products (product_id));
 joinable!(products_tags -> tags (tag_id));
+joinable!(reaction_counts -> products (product_id));
+joinable!(reaction_counts -> reaction_kinds (kind));
+joinable!(reactions -> reaction_kinds (kind));
 joinable!(reactions -> products (product_id));
 joinable!(reactions -> users (user_id));
+joinable!(trending_scores -> products (product_id));
+joinable!(user_reaction_counts -> products (product_id));
+joinable!(user_reaction_counts -> reaction_kinds (kind));
+joinable!(user_reaction_counts -> users (user_id));
+joinable!(webhook_deliveries -> webhooks (webhook_id));
+joinable!(webhooks -> users (user_id));
 
 allow_tables_to_appear_in_same_query!(
+    bookmarks,
+    collection_items,
+    collections,
+    comments,
+    devlogs,
+    notifications,
+    product_links,
+    product_media,
+    product_members,
+    product_revisions,
+    product_slugs,
+    product_views,
     products,
     products_tags,
+    reaction_counts,
+    reaction_kinds,
     reactions,
     tags,
+    trending_scores,
+    user_reaction_counts,
     users,
+    webhook_deliveries,
+    webhooks,
 );
@@ -0,0 +1,48 @@
+use uuid::Uuid;
+
+/// Turns a product title into a url friendly slug.
+///
+/// Only ASCII letters and digits are kept; everything else (including
+/// Japanese characters) becomes a separator. Titles without any ASCII
+/// characters fall back to `product-` followed by the head of the uuid.
+pub fn slugify(title: &str, uuid: &Uuid) -> String {
+    let mut slug = String::new();
+    for c in title.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        format!("product-{}", &uuid.simple().to_string()[..8])
+    } else {
+        slug.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::slugify;
+    use uuid::Uuid;
+
+    #[test]
+    fn slugify_ascii_title() {
+        let uuid = Uuid::new_v4();
+        assert_eq!(slugify("My Todo App!", &uuid), "my-todo-app");
+        assert_eq!(slugify("  Rust -- Rocket  ", &uuid), "rust-rocket");
+    }
+
+    #[test]
+    fn slugify_mixed_title() {
+        let uuid = Uuid::new_v4();
+        assert_eq!(slugify("Railsで作ったTodo", &uuid), "rails-todo");
+    }
+
+    #[test]
+    fn slugify_japanese_title_falls_back_to_uuid() {
+        let uuid = Uuid::parse_str("936da01f-9abd-4d9d-80c7-02af85c822a8").unwrap();
+        assert_eq!(slugify("タスク管理アプリ", &uuid), "product-936da01f");
+    }
+}
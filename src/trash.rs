@@ -0,0 +1,66 @@
+use crate::background;
+use crate::db;
+use crate::error::TentechError;
+use crate::models::product::Product;
+use crate::s3;
+use diesel::pg::PgConnection;
+use rusoto_s3::S3Client;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// How long deleted products can be restored, `TRASH_RETENTION_DAYS` days
+/// (30 by default).
+pub fn retention() -> Duration {
+    let days = background::env_u64("TRASH_RETENTION_DAYS", 30);
+    Duration::from_secs(days * 24 * 60 * 60)
+}
+
+/// When the deleted product is going to be purged.
+pub fn purge_at(product: &Product) -> Option<SystemTime> {
+    product.deleted_at.map(|d| d + retention())
+}
+
+pub fn can_restore(product: &Product) -> bool {
+    purge_at(product).map_or(false, |p| SystemTime::now() < p)
+}
+
+/// Purges up to `limit` products whose retention window is over, removing
+/// their stored media as well.
+pub fn purge_expired(
+    conn: &PgConnection,
+    client: &S3Client,
+    limit: i64,
+) -> Result<usize, TentechError> {
+    let products = db::products::find_expired(conn, SystemTime::now() - retention(), limit)?;
+    for product in products.iter() {
+        let media = db::media::find_by_product_id(conn, &product.id)?;
+        db::products::purge(conn, &product.id)?;
+        // The product is gone either way, so a stored object which cannot be removed is only logged.
+        for m in media.iter().filter(|m| s3::can_delete(m)) {
+            if let Err(e) = s3::delete_object(client, &m.storage_key) {
+                println!("{}: {}", m.storage_key, e);
+            }
+        }
+    }
+    Ok(products.len())
+}
+
+/// Keeps purging expired products in the background, once an hour.
+pub fn spawn_purger() -> thread::JoinHandle<()> {
+    let interval = Duration::from_secs(60 * 60);
+    thread::spawn(move || loop {
+        let conn = background::connect("Trash purger");
+        let client = s3::initial_s3_client();
+        loop {
+            match purge_expired(&conn, &client, 100) {
+                Ok(purged) if purged > 0 => continue,
+                Ok(_) => break,
+                Err(e) => {
+                    println!("Could not purge products: {:?}", e);
+                    break;
+                }
+            }
+        }
+        thread::sleep(interval);
+    })
+}
@@ -0,0 +1,19 @@
+use crate::background;
+use crate::db;
+use crate::db::trending::WINDOWS;
+use std::thread;
+
+/// Keeps the trending scores fresh in the background, refreshing every
+/// `TRENDING_REFRESH_INTERVAL` seconds (ten minutes by default).
+pub fn spawn_refresher() -> thread::JoinHandle<()> {
+    let interval = background::interval("TRENDING_REFRESH_INTERVAL", 10 * 60);
+    thread::spawn(move || loop {
+        let conn = background::connect("Trending refresher");
+        for window in WINDOWS.iter() {
+            if let Err(e) = db::trending::refresh(&conn, window) {
+                println!("Could not refresh trending scores: {:?}", e);
+            }
+        }
+        thread::sleep(interval);
+    })
+}
@@ -1,5 +1,20 @@
+use crate::error::TentechError;
+use std::borrow::Cow;
 use validator::{Validate, ValidationError, ValidationErrors};
 
+/// Fails the request on a single field, for the checks which need more than
+/// the attributes of `Validate`.
+pub fn field_error<M>(field: &'static str, code: &'static str, message: M) -> TentechError
+where
+    M: Into<Cow<'static, str>>,
+{
+    let mut error = ValidationError::new(code);
+    error.message = Some(message.into());
+    let mut errors = ValidationErrors::new();
+    errors.add(field, error);
+    TentechError::ValidationFailed(errors)
+}
+
 #[derive(Debug)]
 pub struct Errors {
     pub errors: ValidationErrors,
@@ -0,0 +1,213 @@
+use crate::background;
+use crate::db;
+use crate::models::webhook::Delivery;
+use crate::net;
+use crypto::hmac::Hmac;
+use crypto::mac::Mac;
+use crypto::sha2::Sha256;
+use diesel::pg::PgConnection;
+use diesel::result::Error;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Client, Method, RedirectPolicy, RequestBuilder, Url};
+use serde_json::Value;
+use std::env;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How many times a delivery is tried before giving up on it.
+const MAX_ATTEMPTS: i32 = 6;
+
+/// Whether webhooks may point at private addresses, which `WEBHOOK_ALLOW_PRIVATE`
+/// allows for receivers on our own network, such as in development.
+pub fn allow_private() -> bool {
+    env::var("WEBHOOK_ALLOW_PRIVATE").map_or(false, |v| v == "true" || v == "1")
+}
+
+/// Hex encoded HMAC-SHA256 of the message.
+pub fn sign(secret: &str, message: &str) -> String {
+    let mut hmac = Hmac::new(Sha256::new(), secret.as_bytes());
+    hmac.input(message.as_bytes());
+    hmac.result()
+        .code()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// How long to wait after `attempts` failed attempts, doubling from 30
+/// seconds, or `None` when there have been too many.
+pub fn backoff(attempts: i32) -> Option<Duration> {
+    if attempts >= MAX_ATTEMPTS {
+        return None;
+    }
+    Some(Duration::from_secs(30 << (attempts.max(1) - 1)))
+}
+
+/// Sends the payload of the delivery with the request, answering the HTTP
+/// status.
+///
+/// `X-Tentech-Signature` signs `<X-Tentech-Timestamp>.<body>`, so that
+/// receivers can check the payload came from us and refuse old ones replayed.
+pub fn send(request: RequestBuilder, secret: &str, delivery: &Delivery) -> Result<u16, String> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| format!("{}", e))?
+        .as_secs();
+    let signed = format!("{}.{}", timestamp, delivery.payload);
+    request
+        .header(CONTENT_TYPE, "application/json")
+        .header("X-Tentech-Event", delivery.event.as_str())
+        .header("X-Tentech-Delivery", delivery.uuid.to_string())
+        .header("X-Tentech-Timestamp", timestamp.to_string())
+        .header(
+            "X-Tentech-Signature",
+            format!("sha256={}", sign(secret, &signed)),
+        )
+        .body(delivery.payload.clone())
+        .send()
+        .map(|r| r.status().as_u16())
+        .map_err(|e| format!("{}", e))
+}
+
+/// Queues the event for every webhook which wants it. Events about a product
+/// pass the tags of the product, for webhooks filtering on them.
+pub fn dispatch(
+    conn: &PgConnection,
+    event: &str,
+    data: Value,
+    tag_ids: &[i32],
+) -> Result<usize, Error> {
+    let webhooks: Vec<_> = db::webhooks::find_by_event(conn, event)?
+        .into_iter()
+        .filter(|w| w.wants(event, tag_ids))
+        .collect();
+    let payload = json!({ "event": event, "data": data }).to_string();
+    for webhook in webhooks.iter() {
+        db::webhooks::enqueue(conn, &webhook.id, event, &payload)?;
+    }
+    Ok(webhooks.len())
+}
+
+/// Builds the request posting to `url`.
+///
+/// The host is checked again on every attempt, since it may have been pointed
+/// at our own network after the webhook was registered, and the delivery goes
+/// to the address it was checked at.
+fn request(client: &Client, url: &str) -> Result<RequestBuilder, String> {
+    let url = Url::parse(url).map_err(|e| format!("{}", e))?;
+    if allow_private() {
+        return Ok(client.post(url));
+    }
+    net::public_request(client, Method::POST, &url)
+}
+
+/// Sends up to `limit` deliveries which are due, scheduling failed ones to be
+/// retried later.
+pub fn deliver_due(conn: &PgConnection, client: &Client, limit: i64) -> Result<usize, Error> {
+    let deliveries = db::webhooks::find_due(conn, SystemTime::now(), limit)?;
+    for (delivery, webhook) in deliveries.iter() {
+        let sent = request(client, &webhook.url)
+            .and_then(|request| send(request, &webhook.secret, delivery));
+        let (status, error) = match sent {
+            Ok(status) if (200..300).contains(&status) => {
+                db::webhooks::record_attempt(
+                    conn,
+                    &delivery.id,
+                    Some(status as i32),
+                    None,
+                    None,
+                    Some(SystemTime::now()),
+                )?;
+                continue;
+            }
+            Ok(status) => (Some(status as i32), None),
+            Err(e) => (None, Some(e)),
+        };
+        let next_attempt_at = backoff(delivery.attempts + 1).map(|d| SystemTime::now() + d);
+        db::webhooks::record_attempt(conn, &delivery.id, status, error, next_attempt_at, None)?;
+    }
+    Ok(deliveries.len())
+}
+
+/// Keeps sending queued deliveries in the background, checking every
+/// `WEBHOOK_INTERVAL` seconds (10 by default).
+pub fn spawn_dispatcher() -> thread::JoinHandle<()> {
+    let interval = background::interval("WEBHOOK_INTERVAL", 10);
+    // A redirect could lead to our own network, bypassing the check of the url.
+    let client = Client::builder()
+        .timeout(Duration::from_secs(10))
+        .redirect(RedirectPolicy::none())
+        .build()
+        .unwrap();
+    thread::spawn(move || loop {
+        let conn = background::connect("Webhook dispatcher");
+        loop {
+            match deliver_due(&conn, &client, 100) {
+                Ok(sent) if sent > 0 => continue,
+                Ok(_) => break,
+                Err(e) => {
+                    println!("Could not deliver webhooks: {:?}", e);
+                    break;
+                }
+            }
+        }
+        thread::sleep(interval);
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{backoff, send, sign};
+    use crate::models::webhook::Delivery;
+    use crate::net::stub_server;
+    use reqwest::Client;
+    use std::time::{Duration, SystemTime};
+    use uuid::Uuid;
+
+    #[test]
+    fn sign_matches_known_digest() {
+        assert_eq!(
+            sign("key", "The quick brown fox jumps over the lazy dog"),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[test]
+    fn backoff_doubles_until_giving_up() {
+        assert_eq!(backoff(1), Some(Duration::from_secs(30)));
+        assert_eq!(backoff(3), Some(Duration::from_secs(120)));
+        assert_eq!(backoff(6), None);
+    }
+
+    #[test]
+    fn send_posts_signed_payload() {
+        let (url, received) = stub_server("204 No Content", 1);
+        let delivery = Delivery {
+            id: 1,
+            uuid: Uuid::new_v4(),
+            webhook_id: 1,
+            event: "product.created".to_string(),
+            payload: "{\"event\":\"product.created\"}".to_string(),
+            attempts: 0,
+            status: None,
+            error: None,
+            next_attempt_at: None,
+            delivered_at: None,
+            created_at: SystemTime::now(),
+        };
+        let url = format!("{}/hook", url);
+        assert_eq!(send(Client::new().post(&url), "secret", &delivery), Ok(204));
+        let request = received.recv().unwrap().to_lowercase();
+        assert!(request.starts_with("post /hook "));
+        assert!(request.contains("x-tentech-event: product.created"));
+        let timestamp = request
+            .lines()
+            .filter_map(|l| l.trim().splitn(2, "x-tentech-timestamp: ").nth(1))
+            .next()
+            .unwrap();
+        assert!(request.contains(&format!(
+            "x-tentech-signature: sha256={}",
+            sign("secret", &format!("{}.{}", timestamp, delivery.payload))
+        )));
+    }
+}